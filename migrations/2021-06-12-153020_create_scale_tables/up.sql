@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE scale_notes (
+    scale TEXT NOT NULL,
+    degree INTEGER NOT NULL,
+    interval INTEGER NOT NULL,
+    PRIMARY KEY (scale, interval)
+) WITHOUT ROWID;
+
+CREATE TABLE scale_names (
+    scale TEXT NOT NULL,
+    alternative_name TEXT PRIMARY KEY
+) WITHOUT ROWID;
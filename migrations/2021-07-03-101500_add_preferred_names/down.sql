@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+CREATE TABLE names_backup (
+    chord TEXT NOT NULL,
+    alternative_name TEXT PRIMARY KEY
+) WITHOUT ROWID;
+
+INSERT INTO names_backup SELECT chord, alternative_name FROM names;
+
+DROP TABLE names;
+
+ALTER TABLE names_backup RENAME TO names;
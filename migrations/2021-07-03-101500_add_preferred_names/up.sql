@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE names ADD COLUMN preferred BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,12 @@
+-- This file should undo anything in `up.sql`
+CREATE TABLE names_backup (
+    chord TEXT NOT NULL,
+    alternative_name TEXT PRIMARY KEY,
+    preferred BOOLEAN NOT NULL DEFAULT 0
+) WITHOUT ROWID;
+
+INSERT INTO names_backup SELECT chord, alternative_name, preferred FROM names;
+
+DROP TABLE names;
+
+ALTER TABLE names_backup RENAME TO names;
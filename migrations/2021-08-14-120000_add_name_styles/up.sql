@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE names ADD COLUMN style TEXT NOT NULL DEFAULT 'pop';
@@ -0,0 +1,644 @@
+use crate::music_theory::*;
+
+/// Returns the roman numeral of a chord with the given `quality`, built on
+/// the `degree` of a key, such as "ii7" or "viiø7". Chords with a minor
+/// third are written in lower case.
+#[allow(non_upper_case_globals)]
+pub fn roman_numeral(degree: Degree, quality: &[Interval]) -> String {
+    use intervals::*;
+    let interval_of = |d: Degree| quality.iter().find(|i| i.number == d).copied();
+
+    let numeral = degree_to_string(degree);
+    let numeral = if interval_of(degrees::III) == Some(Min3rd) {
+        numeral.to_lowercase()
+    } else {
+        numeral
+    };
+
+    let mut suffix = match (interval_of(degrees::V), interval_of(degrees::VII)) {
+        (Some(Dim5th), Some(Min7th)) => "ø7",
+        (Some(Dim5th), Some(Dim7th)) => "°7",
+        (Some(Dim5th), None) => "°",
+        (Some(Aug5th), Some(Maj7th)) => "+maj7",
+        (Some(Aug5th), Some(Min7th)) => "+7",
+        (Some(Aug5th), None) => "+",
+        (_, Some(Maj7th)) => "maj7",
+        (_, Some(_)) => "7",
+        (_, None) => "",
+    }
+    .to_string();
+
+    match interval_of(degrees::IX) {
+        Some(Maj9th) if suffix.ends_with('7') => {
+            suffix.pop();
+            suffix.push('9');
+        }
+        Some(Maj9th) => suffix.push_str("add9"),
+        Some(Min9th) => suffix.push_str("b9"),
+        Some(Aug9th) => suffix.push_str("#9"),
+        _ => {}
+    }
+
+    numeral + &suffix
+}
+
+/// How a chord relates to the key it is analysed in.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Relation {
+    /// Every note of the chord is in the key. In minor keys, the raised
+    /// sixth and seventh degrees also count.
+    Diatonic,
+    /// A dominant or leading-tone chord of a diatonic chord other than the
+    /// tonic, such as V7/V.
+    Secondary,
+    /// Borrowed from the parallel major or minor key, such as iv in a major
+    /// key.
+    Borrowed,
+    /// None of the above.
+    Chromatic,
+}
+
+/// The roman numeral analysis of a single chord.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Analysis {
+    /// Roman numeral, with inversion figures, e.g. "ii65" or "V7/V".
+    pub numeral: String,
+    pub relation: Relation,
+}
+
+/// Returns the parallel key: the minor key with the same tonic for a major
+/// key, and vice versa.
+fn parallel(key: &Key) -> Key {
+    if key.is_minor() {
+        Key::major(key.tonic())
+    } else {
+        Key::minor(key.tonic())
+    }
+}
+
+/// Returns the notes the key's chords may use on each degree, from the
+/// first to the seventh. Minor keys may also raise their sixth and seventh
+/// degrees, as in the harmonic and melodic minor scales.
+fn degree_notes(key: &Key) -> Vec<Vec<SpelledNote>> {
+    let mut notes: Vec<Vec<SpelledNote>> = key
+        .scale()
+        .spelled_notes()
+        .into_iter()
+        .map(|(n, _)| vec![n])
+        .collect();
+    if key.is_minor() {
+        for degree in &[degrees::VI, degrees::VII] {
+            let raised = notes[*degree as usize - 1][0];
+            notes[*degree as usize - 1]
+                .push(SpelledNote::new(raised.natural, raised.accidental + 1));
+        }
+    }
+    notes
+}
+
+/// Returns the pitch classes of the notes the key's chords may use.
+fn pitch_classes(key: &Key) -> Vec<i32> {
+    degree_notes(key)
+        .iter()
+        .flatten()
+        .map(|n| n.semitones().rem_euclid(12))
+        .collect()
+}
+
+/// Returns true if every note of the chord is in the key.
+fn is_diatonic(key: &Key, chord: &Chord) -> bool {
+    let allowed = pitch_classes(key);
+    chord
+        .notes()
+        .iter()
+        .all(|n| allowed.contains(&pitch_class(n.letter())))
+}
+
+/// Returns the degree of the key that `note` is spelled as, along with the
+/// accidental needed to reach it from the note of the key on that degree:
+/// Bb is the seventh degree of C major, flattened.
+pub(crate) fn degree_of(key: &Key, note: SpelledNote) -> (Degree, Accidental) {
+    let tonic = SpelledNote::from_letter(key.tonic());
+    let degree = (note.natural.index() - tonic.natural.index()).rem_euclid(7) + 1;
+    let candidates = &degree_notes(key)[degree as usize - 1];
+    let accidental = if candidates
+        .iter()
+        .any(|c| c.semitones().rem_euclid(12) == note.semitones().rem_euclid(12))
+    {
+        0
+    } else {
+        let difference = (note.semitones() - candidates[0].semitones()).rem_euclid(12);
+        if difference > 6 {
+            difference - 12
+        } else {
+            difference
+        }
+    };
+    (degree, accidental)
+}
+
+/// Returns the figures showing which note of a triad or seventh chord is in
+/// the bass, e.g. "6" for a triad in first inversion or "43" for a seventh
+/// chord in second inversion. Other chords get no figures.
+fn inversion_figures(chord: &Chord) -> (&'static str, &'static str) {
+    use degrees::*;
+    let seventh = chord.quality.iter().any(|i| i.number == VII);
+    let plain = chord
+        .quality
+        .iter()
+        .all(|i| i.number == III || i.number == V || i.number == VII);
+    let bass_degree = chord.bass.and_then(|bass| {
+        std::iter::once(I)
+            .chain(chord.quality.iter().map(|i| i.number))
+            .zip(chord.with_bass(None).notes())
+            .find(|(_, n)| n.letter() == bass)
+            .map(|(d, _)| d)
+    });
+
+    match (plain, seventh, bass_degree) {
+        (false, _, _) => ("", ""),
+        (true, false, Some(III)) => ("", "6"),
+        (true, false, Some(V)) => ("", "64"),
+        (true, true, Some(III)) => ("7", "65"),
+        (true, true, Some(V)) => ("7", "43"),
+        (true, true, Some(VII)) => ("7", "42"),
+        _ => ("", ""),
+    }
+}
+
+/// Returns the roman numeral of the chord on `degree`, with `accidental`
+/// written before it and inversion figures in place of the seventh.
+fn numeral_with_figures(degree: Degree, accidental: Accidental, chord: &Chord) -> String {
+    let prefix = match accidental {
+        a if a < 0 => "b".repeat(a.unsigned_abs() as usize),
+        a => "#".repeat(a as usize),
+    };
+    let mut numeral = roman_numeral(degree, &chord.quality);
+    let (replaced, figures) = inversion_figures(chord);
+    if !figures.is_empty() {
+        numeral.truncate(numeral.len() - replaced.len());
+        numeral.push_str(figures);
+    }
+    prefix + &numeral
+}
+
+/// Returns true if the chord can act as a dominant: a major triad, with or
+/// without a minor seventh and ninths.
+pub(crate) fn is_dominant(quality: &[Interval]) -> bool {
+    use intervals::*;
+    quality.contains(&Maj3rd)
+        && quality.contains(&Per5th)
+        && !quality.contains(&Maj7th)
+        && !quality.iter().any(|i| i.number == degrees::VI)
+}
+
+/// Returns true if the chord can act as a leading-tone chord: a diminished
+/// triad, with or without a diminished or minor seventh.
+pub(crate) fn is_leading_tone(quality: &[Interval]) -> bool {
+    use intervals::*;
+    quality.contains(&Min3rd) && quality.contains(&Dim5th) && !quality.contains(&Maj7th)
+}
+
+/// Analyses the chord as a secondary dominant or leading-tone chord of one
+/// of the key's degrees, returning a numeral such as "V7/V". The tonic and
+/// diminished chords cannot be tonicised.
+fn secondary(key: &Key, chord: &Chord) -> Option<String> {
+    use intervals::*;
+    let root = SpelledNote::from_letter(chord.root_letter());
+    let (function, target) = if is_dominant(&chord.quality) {
+        (degrees::V, root.above(Per4th))
+    } else if is_leading_tone(&chord.quality) {
+        (degrees::VII, root.above(Min2nd))
+    } else {
+        return None;
+    };
+
+    let (degree, accidental) = degree_of(key, target);
+    let target_chord = &key.diatonic_chords(ChordSize::Triad)[degree as usize - 1];
+    if accidental != 0 || degree == degrees::I || is_leading_tone(&target_chord.quality) {
+        return None;
+    }
+
+    Some(format!(
+        "{}/{}",
+        numeral_with_figures(function, 0, chord),
+        roman_numeral(degree, &target_chord.quality)
+    ))
+}
+
+/// Labels each chord with its roman numeral in `key`, including inversion
+/// figures, and marks secondary and borrowed chords as such.
+pub fn analyze(key: &Key, chords: &[Chord]) -> Vec<Analysis> {
+    chords
+        .iter()
+        .map(|chord| {
+            let root = SpelledNote::from_letter(chord.root_letter());
+            let (degree, accidental) = degree_of(key, root);
+            let numeral = numeral_with_figures(degree, accidental, chord);
+
+            if is_diatonic(key, chord) {
+                Analysis {
+                    numeral,
+                    relation: Relation::Diatonic,
+                }
+            } else if let Some(numeral) = secondary(key, chord) {
+                Analysis {
+                    numeral,
+                    relation: Relation::Secondary,
+                }
+            } else if is_diatonic(&parallel(key), chord) {
+                Analysis {
+                    numeral,
+                    relation: Relation::Borrowed,
+                }
+            } else {
+                Analysis {
+                    numeral,
+                    relation: Relation::Chromatic,
+                }
+            }
+        })
+        .collect()
+}
+
+/// A key that a progression could be in, along with how likely it is.
+#[derive(PartialEq, Clone, Debug)]
+pub struct KeyEstimate {
+    pub key: Key,
+    /// From 0 to 1. The confidences of every key add up to 1.
+    pub confidence: f64,
+}
+
+/// The point in a progression where its key changes.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Modulation {
+    /// Index of the first chord in the new key.
+    pub index: usize,
+    pub key: Key,
+}
+
+/// Points lost for every change of key when looking for modulations, so
+/// that a single chromatic chord does not count as one.
+const MODULATION_COST: f64 = 4.0;
+
+/// Returns the number of semitones from the tonic of the key up to the root
+/// of the chord, from 0 to 11.
+pub(crate) fn root_distance(key: &Key, chord: &Chord) -> Semitones {
+    (pitch_class(chord.root_letter()) - pitch_class(key.tonic())).rem_euclid(12)
+}
+
+/// Returns true if the chord is the tonic triad of the key, or the tonic
+/// triad with notes added.
+pub(crate) fn is_tonic(key: &Key, chord: &Chord) -> bool {
+    let third = if key.is_minor() {
+        intervals::Min3rd
+    } else {
+        intervals::Maj3rd
+    };
+    root_distance(key, chord) == 0 && chord.quality.contains(&third)
+}
+
+/// Returns true if the chord can act as the dominant of the key, e.g. G7
+/// in both C major and C minor.
+pub(crate) fn is_key_dominant(key: &Key, chord: &Chord) -> bool {
+    root_distance(key, chord) == intervals::Per5th.semitones() && is_dominant(&chord.quality)
+}
+
+/// Returns true if the chord is built on the fourth degree of the key.
+fn is_subdominant(key: &Key, chord: &Chord) -> bool {
+    root_distance(key, chord) == intervals::Per4th.semitones()
+}
+
+/// Scores how well the `i`th chord fits in the key, from how many of its
+/// notes belong to the key, whether it is the tonic chord, and whether it
+/// ends a cadence. Tonic chords count for more at the start and end.
+fn chord_score(key: &Key, chords: &[Chord], i: usize) -> f64 {
+    let allowed = pitch_classes(key);
+    let chord = &chords[i];
+    let notes = chord.notes();
+    let fitting = notes
+        .iter()
+        .filter(|n| allowed.contains(&pitch_class(n.letter())))
+        .count();
+    let mut score = 2.0 * fitting as f64 / notes.len() as f64 - 1.0;
+
+    if is_tonic(key, chord) {
+        score += 1.0;
+        if i == 0 || i == chords.len() - 1 {
+            score += 1.0;
+        }
+        if i > 0 && is_key_dominant(key, &chords[i - 1]) {
+            score += 2.0;
+        } else if i > 0 && is_subdominant(key, &chords[i - 1]) {
+            score += 1.0;
+        }
+    }
+    score
+}
+
+/// Returns the 24 major and minor keys. Tonics are spelled like the roots
+/// of the chords where possible, so that a progression in Gb is not said to
+/// be in F#.
+fn candidate_keys(chords: &[Chord]) -> Vec<Key> {
+    use pitch_calc::Letter::*;
+    let major = [C, Db, D, Eb, E, F, Gb, G, Ab, A, Bb, B];
+    let minor = [C, Csh, D, Eb, E, F, Fsh, G, Gsh, A, Bb, B];
+    let spelled = |tonic: Letter| {
+        chords
+            .iter()
+            .map(|c| c.root_letter())
+            .find(|root| pitch_class(*root) == pitch_class(tonic))
+            .unwrap_or(tonic)
+    };
+    major
+        .iter()
+        .map(|t| Key::major(spelled(*t)))
+        .chain(minor.iter().map(|t| Key::minor(spelled(*t))))
+        .collect()
+}
+
+/// Ranks every major and minor key by how likely the progression is to be
+/// in it, with the most likely first. Each chord counts in proportion to its
+/// `weight`, e.g. its length in beats, or the same as the others if the
+/// weights do not add up to more than zero. Keys score points for chords
+/// which fit in them, for starting and ending on their tonic chord, and for
+/// cadences onto the tonic. Returns nothing unless there is one weight for
+/// every chord.
+pub fn detect_keys_weighted(chords: &[Chord], weights: &[f64]) -> Vec<KeyEstimate> {
+    if chords.is_empty() || weights.len() != chords.len() {
+        return vec![];
+    }
+    let sum = weights.iter().sum::<f64>();
+    let weights: Vec<f64> = if sum > 0.0 {
+        let mean = sum / weights.len() as f64;
+        weights.iter().map(|w| w / mean).collect()
+    } else {
+        vec![1.0; chords.len()]
+    };
+    let scores: Vec<(Key, f64)> = candidate_keys(chords)
+        .into_iter()
+        .map(|key| {
+            let score = weights
+                .iter()
+                .enumerate()
+                .map(|(i, weight)| chord_score(&key, chords, i) * weight)
+                .sum();
+            (key, score)
+        })
+        .collect();
+
+    // Turn the scores into confidences that add up to 1.
+    let best = scores.iter().map(|(_, s)| *s).fold(f64::MIN, f64::max);
+    let total: f64 = scores.iter().map(|(_, s)| (s - best).exp()).sum();
+    let mut estimates: Vec<KeyEstimate> = scores
+        .into_iter()
+        .map(|(key, score)| KeyEstimate {
+            key,
+            confidence: (score - best).exp() / total,
+        })
+        .collect();
+    estimates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    estimates
+}
+
+/// Ranks every major and minor key by how likely the chords are to be in
+/// it, with the most likely first. Every chord counts the same.
+pub fn detect_keys(chords: &[Chord]) -> Vec<KeyEstimate> {
+    detect_keys_weighted(chords, &vec![1.0; chords.len()])
+}
+
+/// Finds the key of every part of the progression, returning the opening
+/// key at index 0 followed by every point where the key changes. Keys are
+/// chosen so that the chords fit them as well as possible, while losing
+/// points for every change of key.
+pub fn modulations(chords: &[Chord]) -> Vec<Modulation> {
+    if chords.is_empty() {
+        return vec![];
+    }
+    let keys = candidate_keys(chords);
+
+    // Best score of any sequence of keys ending on each key, and the key
+    // the previous chord was in.
+    let mut scores: Vec<f64> = keys.iter().map(|k| chord_score(k, chords, 0)).collect();
+    let mut paths: Vec<Vec<usize>> = vec![];
+    for i in 1..chords.len() {
+        let mut new_scores = vec![];
+        let mut from = vec![];
+        for (k, key) in keys.iter().enumerate() {
+            let (best, score) = scores
+                .iter()
+                .enumerate()
+                .map(|(p, s)| (p, if p == k { *s } else { s - MODULATION_COST }))
+                .fold((k, f64::MIN), |a, b| if b.1 > a.1 { b } else { a });
+            new_scores.push(score + chord_score(key, chords, i));
+            from.push(best);
+        }
+        scores = new_scores;
+        paths.push(from);
+    }
+
+    // Follow the best sequence back from the last chord.
+    let mut index = (0..keys.len()).fold(0, |a, b| if scores[b] > scores[a] { b } else { a });
+    let mut chosen = vec![index];
+    for from in paths.iter().rev() {
+        index = from[index];
+        chosen.push(index);
+    }
+    chosen.reverse();
+
+    chosen
+        .iter()
+        .enumerate()
+        .filter(|(i, k)| *i == 0 || chosen[i - 1] != **k)
+        .map(|(i, k)| Modulation {
+            index: i,
+            key: keys[*k].clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music_theory::tests::chord;
+    use pitch_calc::Letter::*;
+
+    #[test]
+    fn diatonic_numerals() {
+        let numerals = |key: Key, size: ChordSize| -> Vec<String> {
+            key.diatonic_chords(size)
+                .iter()
+                .zip(1..)
+                .map(|(chord, degree)| roman_numeral(degree, &chord.quality))
+                .collect()
+        };
+
+        assert_eq!(
+            numerals(Key::major(Eb), ChordSize::Seventh),
+            vec!["Imaj7", "ii7", "iii7", "IVmaj7", "V7", "vi7", "viiø7"]
+        );
+        assert_eq!(
+            numerals(Key::minor(A), ChordSize::Triad),
+            vec!["i", "ii°", "III", "iv", "v", "VI", "VII"]
+        );
+        assert_eq!(
+            numerals(Key::major(C), ChordSize::Ninth),
+            vec!["Imaj9", "ii9", "iii7b9", "IVmaj9", "V9", "vi9", "viiø7b9"]
+        );
+    }
+
+    fn numerals(key: &Key, chords: &[Chord]) -> Vec<(String, Relation)> {
+        analyze(key, chords)
+            .into_iter()
+            .map(|a| (a.numeral, a.relation))
+            .collect()
+    }
+
+    fn labelled(numerals: &[(&str, Relation)]) -> Vec<(String, Relation)> {
+        numerals.iter().map(|(n, r)| (n.to_string(), *r)).collect()
+    }
+
+    #[test]
+    fn progression_numerals() {
+        use intervals::*;
+        use Relation::*;
+        let major = vec![Maj3rd, Per5th];
+        let minor = vec![Min3rd, Per5th];
+        let dom7 = vec![Maj3rd, Per5th, Min7th];
+        let dim7 = vec![Min3rd, Dim5th, Dim7th];
+
+        let chords = vec![
+            chord(D, vec![Min3rd, Per5th, Min7th]).with_bass(Some(F)),
+            chord(G, dom7.clone()).with_bass(Some(D)),
+            chord(C, vec![Maj3rd, Per5th, Maj7th]),
+            chord(A, dom7.clone()),
+            chord(Fsh, dim7.clone()),
+            chord(Bb, major.clone()),
+            chord(F, minor.clone()),
+            chord(C, major.clone()).with_bass(Some(G)),
+            chord(Db, major.clone()),
+        ];
+        let expected = labelled(&[
+            ("ii65", Diatonic),
+            ("V43", Diatonic),
+            ("Imaj7", Diatonic),
+            ("V7/ii", Secondary),
+            ("vii°7/V", Secondary),
+            ("bVII", Borrowed),
+            ("iv", Borrowed),
+            ("I64", Diatonic),
+            ("bII", Chromatic),
+        ]);
+        assert_eq!(numerals(&Key::major(C), &chords), expected);
+
+        // The raised sixth and seventh degrees belong to minor keys.
+        let chords = vec![
+            chord(A, minor.clone()).with_bass(Some(C)),
+            chord(Gsh, dim7),
+            chord(E, dom7.clone()).with_bass(Some(D)),
+            chord(F, major.clone()),
+            chord(D, major),
+        ];
+        let expected = labelled(&[
+            ("i6", Diatonic),
+            ("vii°7", Diatonic),
+            ("V42", Diatonic),
+            ("VI", Diatonic),
+            ("IV", Diatonic),
+        ]);
+        assert_eq!(numerals(&Key::minor(A), &chords), expected);
+    }
+
+    #[test]
+    fn key_detection() {
+        use intervals::*;
+        let major = vec![Maj3rd, Per5th];
+        let minor = vec![Min3rd, Per5th];
+        let dom7 = vec![Maj3rd, Per5th, Min7th];
+
+        // Starting away from the tonic does not fool it.
+        let chords = vec![
+            chord(F, major.clone()),
+            chord(G, dom7.clone()),
+            chord(C, major.clone()),
+        ];
+        let keys = detect_keys(&chords);
+        assert_eq!(keys[0].key, Key::major(C));
+        assert!(keys[0].confidence > 0.5);
+        let total: f64 = keys.iter().map(|k| k.confidence).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        // The relative minor uses the same notes, but its own tonic and
+        // dominant.
+        let chords = vec![
+            chord(A, minor.clone()),
+            chord(D, minor.clone()),
+            chord(E, dom7.clone()),
+            chord(A, minor.clone()),
+        ];
+        assert_eq!(detect_keys(&chords)[0].key, Key::minor(A));
+
+        // Tonics are spelled like the chords.
+        let chords = vec![
+            chord(Gb, major.clone()),
+            chord(Db, dom7.clone()),
+            chord(Gb, major.clone()),
+        ];
+        assert_eq!(detect_keys(&chords)[0].key, Key::major(Gb));
+
+        // Longer chords count for more.
+        let chords = vec![chord(C, major.clone()), chord(G, major.clone())];
+        assert_eq!(
+            detect_keys_weighted(&chords, &[1.0, 8.0])[0].key,
+            Key::major(G)
+        );
+        assert!(detect_keys(&[]).is_empty());
+
+        // Weights which are missing, or add up to nothing, cannot be used.
+        assert!(detect_keys_weighted(&chords, &[1.0]).is_empty());
+        assert!(detect_keys_weighted(&chords, &[1.0, 2.0, 3.0]).is_empty());
+        for weights in &[[0.0, 0.0], [-1.0, 1.0], [f64::NAN, 1.0]] {
+            assert_eq!(detect_keys_weighted(&chords, weights), detect_keys(&chords));
+        }
+    }
+
+    #[test]
+    fn modulation_points() {
+        use intervals::*;
+        let major = vec![Maj3rd, Per5th];
+        let dom7 = vec![Maj3rd, Per5th, Min7th];
+        let chords = vec![
+            chord(C, major.clone()),
+            chord(F, major.clone()),
+            chord(G, dom7.clone()),
+            chord(C, major.clone()),
+            chord(Eb, major.clone()),
+            chord(Ab, major.clone()),
+            chord(Bb, dom7.clone()),
+            chord(Eb, major.clone()),
+        ];
+        assert_eq!(
+            modulations(&chords),
+            vec![
+                Modulation {
+                    index: 0,
+                    key: Key::major(C)
+                },
+                Modulation {
+                    index: 4,
+                    key: Key::major(Eb)
+                },
+            ]
+        );
+
+        // A single secondary dominant is not a change of key.
+        let chords = vec![
+            chord(C, major.clone()),
+            chord(A, dom7),
+            chord(D, vec![Min3rd, Per5th]),
+            chord(G, major.clone()),
+            chord(C, major),
+        ];
+        assert_eq!(modulations(&chords).len(), 1);
+    }
+}
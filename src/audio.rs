@@ -0,0 +1,375 @@
+use crate::sampler::{to_i16, Frame, Sample, Sampler, SAMPLE_RATE};
+use crate::sequencer::Message;
+use portaudio as pa;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Number of frames worked out by the sampler at a time. Smaller buffers
+/// make notes start sooner, at the cost of more work.
+pub const FRAMES_PER_BUFFER: usize = 1024;
+
+/// Somewhere the sound made by the sampler can go.
+pub trait AudioBackend {
+    /// Plays or stores the frames. Backends which play in real time block
+    /// until there is room for them.
+    fn write(&mut self, frames: &[Frame]) -> Result<(), Box<dyn Error>>;
+
+    /// Returns true if `write` keeps time by itself. Frames are written to
+    /// other backends as fast as they would be played.
+    fn is_real_time(&self) -> bool {
+        false
+    }
+
+    /// Called once no more frames will be written.
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Throws the frames away. Useful for tests, and when there is no sound
+/// card.
+pub struct NullBackend;
+
+impl AudioBackend for NullBackend {
+    fn write(&mut self, _frames: &[Frame]) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Records the frames to a 16-bit stereo WAV file.
+pub struct WavBackend {
+    writer: Option<hound::WavWriter<BufWriter<File>>>,
+}
+
+impl WavBackend {
+    /// Creates the file, replacing it if it exists.
+    pub fn create<P: AsRef<Path>>(path: P, rate: f64) -> Result<WavBackend, hound::Error> {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: rate as u32,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        Ok(WavBackend {
+            writer: Some(hound::WavWriter::create(path, spec)?),
+        })
+    }
+}
+
+impl AudioBackend for WavBackend {
+    fn write(&mut self, frames: &[Frame]) -> Result<(), Box<dyn Error>> {
+        if let Some(writer) = &mut self.writer {
+            for sample in frames.iter().flatten() {
+                writer.write_sample(to_i16(*sample))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        match self.writer.take() {
+            Some(writer) => Ok(writer.finalize()?),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Writes the frames as raw 16-bit little-endian stereo samples, as read by
+/// `aplay -f cd`.
+pub struct PcmBackend<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> PcmBackend<W> {
+    pub fn new(writer: W) -> PcmBackend<W> {
+        PcmBackend { writer }
+    }
+}
+
+impl PcmBackend<std::io::Stdout> {
+    /// Writes the frames to the standard output.
+    pub fn stdout() -> PcmBackend<std::io::Stdout> {
+        PcmBackend::new(std::io::stdout())
+    }
+}
+
+impl<W: Write> AudioBackend for PcmBackend<W> {
+    fn write(&mut self, frames: &[Frame]) -> Result<(), Box<dyn Error>> {
+        let bytes: Vec<u8> = frames
+            .iter()
+            .flatten()
+            .flat_map(|s| to_i16(*s).to_le_bytes())
+            .collect();
+        self.writer.write_all(&bytes)?;
+        Ok(self.writer.flush()?)
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(self.writer.flush()?)
+    }
+}
+
+/// Plays the frames on the default sound card.
+pub struct PortAudioBackend {
+    stream: pa::Stream<pa::Blocking<pa::stream::Buffer>, pa::Output<f32>>,
+}
+
+impl PortAudioBackend {
+    /// Opens and starts a stereo stream on the default output device.
+    pub fn open(rate: f64) -> Result<PortAudioBackend, pa::Error> {
+        let pa = pa::PortAudio::new()?;
+        // The default device is looked up through the host API, as asking
+        // for it directly panics when there is none.
+        let device = pa
+            .host_api_info(pa.default_host_api()?)
+            .and_then(|info| info.default_output_device)
+            .ok_or(pa::Error::DeviceUnavailable)?;
+        let latency = pa.device_info(device)?.default_low_output_latency;
+        let params = pa::StreamParameters::<f32>::new(device, 2, true, latency);
+        let settings = pa::OutputStreamSettings::new(params, rate, FRAMES_PER_BUFFER as u32);
+        let mut stream = pa.open_blocking_stream(settings)?;
+        stream.start()?;
+        Ok(PortAudioBackend { stream })
+    }
+}
+
+impl AudioBackend for PortAudioBackend {
+    fn write(&mut self, frames: &[Frame]) -> Result<(), Box<dyn Error>> {
+        let result = self.stream.write(frames.len() as u32, |buffer| {
+            for (out, sample) in buffer.iter_mut().zip(frames.iter().flatten()) {
+                *out = *sample;
+            }
+        });
+        match result {
+            // A late buffer is heard as a click, but the stream goes on.
+            Ok(()) | Err(pa::Error::OutputUnderflowed) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn is_real_time(&self) -> bool {
+        true
+    }
+}
+
+/// The backends that can be chosen, written as "portaudio", "wav:FILE",
+/// "pcm" or "null".
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub enum Output {
+    #[default]
+    PortAudio,
+    Wav(PathBuf),
+    Pcm,
+    Null,
+}
+
+impl Output {
+    /// Opens the backend.
+    pub fn open(&self, rate: f64) -> Result<Box<dyn AudioBackend>, Box<dyn Error>> {
+        Ok(match self {
+            Output::PortAudio => Box::new(PortAudioBackend::open(rate)?),
+            Output::Wav(path) => Box::new(WavBackend::create(path, rate)?),
+            Output::Pcm => Box::new(PcmBackend::stdout()),
+            Output::Null => Box::new(NullBackend),
+        })
+    }
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Output::PortAudio => write!(f, "portaudio"),
+            Output::Wav(path) => write!(f, "wav:{}", path.display()),
+            Output::Pcm => write!(f, "pcm"),
+            Output::Null => write!(f, "null"),
+        }
+    }
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "portaudio" => Ok(Output::PortAudio),
+            "pcm" => Ok(Output::Pcm),
+            "null" | "none" => Ok(Output::Null),
+            _ => match s.strip_prefix("wav:") {
+                Some(path) if !path.is_empty() => Ok(Output::Wav(PathBuf::from(path))),
+                _ => Err(format!(
+                    "Unknown audio output {}, try portaudio, wav:FILE, pcm or null",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+/// Plays the messages from the sequencer through a sampler, on a thread of
+/// its own.
+pub struct Player {
+    /// The backend in use, which is the null backend if the one asked for
+    /// could not be opened.
+    pub output: Output,
+    handle: JoinHandle<()>,
+}
+
+impl Player {
+    /// Starts playing the messages received through the sampler on the
+    /// output. If the output cannot be opened, the null backend is used
+    /// instead, and the reason is returned along with the player. Playing
+    /// stops once the sender of the messages is dropped.
+    pub fn start(
+        messages: Receiver<Message>,
+        sample: Sample,
+        output: Output,
+    ) -> (Player, Option<String>) {
+        let (opened_tx, opened_rx) = channel();
+        // Some backends cannot be moved between threads, so they are opened
+        // on the thread that uses them.
+        let requested = output.clone();
+        let handle = std::thread::spawn(move || {
+            let backend = match requested.open(SAMPLE_RATE) {
+                Ok(backend) => {
+                    opened_tx.send((requested, None)).unwrap();
+                    backend
+                }
+                Err(e) => {
+                    opened_tx.send((Output::Null, Some(e.to_string()))).unwrap();
+                    Box::new(NullBackend)
+                }
+            };
+            if let Err(e) = play(messages, Sampler::new(sample, SAMPLE_RATE), backend) {
+                eprintln!("Audio output stopped: {}", e);
+            }
+        });
+        let (output, error) = opened_rx.recv().unwrap();
+        (Player { output, handle }, error)
+    }
+
+    /// Waits for playing to stop, once the sender of the messages has been
+    /// dropped.
+    pub fn finish(self) {
+        self.handle.join().ok();
+    }
+}
+
+/// Plays the messages through the sampler on the backend until the sender
+/// of the messages is dropped.
+fn play(
+    messages: Receiver<Message>,
+    mut sampler: Sampler,
+    mut backend: Box<dyn AudioBackend>,
+) -> Result<(), Box<dyn Error>> {
+    let mut buffer = vec![[0.0; 2]; FRAMES_PER_BUFFER];
+    let start = Instant::now();
+    let mut written = 0;
+    loop {
+        loop {
+            match messages.try_recv() {
+                Ok(message) => sampler.handle(message),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return backend.finish(),
+            }
+        }
+
+        for frame in buffer.iter_mut() {
+            *frame = [0.0; 2];
+        }
+        sampler.fill_slice(&mut buffer);
+        backend.write(&buffer)?;
+        written += buffer.len();
+
+        if !backend.is_real_time() {
+            let due = start + Duration::from_secs_f64(written as f64 / SAMPLE_RATE);
+            if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                std::thread::sleep(wait);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::Instrument;
+    use pitch_calc::{Letter, LetterOctave};
+
+    #[test]
+    fn outputs() {
+        for output in &["portaudio", "wav:session.wav", "pcm", "null"] {
+            assert_eq!(output.parse::<Output>().unwrap().to_string(), *output);
+        }
+        assert_eq!("none".parse(), Ok(Output::Null));
+        assert!("wav:".parse::<Output>().is_err());
+        assert!("speakers".parse::<Output>().is_err());
+    }
+
+    #[test]
+    fn backends() {
+        let frames = [[0.5, -0.5], [2.0, -2.0]];
+
+        let mut pcm = PcmBackend::new(vec![]);
+        pcm.write(&frames).unwrap();
+        let mut expected = vec![];
+        for sample in &[16383i16, -16383, i16::MAX, -i16::MAX] {
+            expected.extend_from_slice(&sample.to_le_bytes());
+        }
+        assert_eq!(pcm.writer, expected);
+
+        let path = std::env::temp_dir().join(format!(
+            "harmony_explorer_backend_test_{}.wav",
+            std::process::id()
+        ));
+        let mut wav = WavBackend::create(&path, SAMPLE_RATE).unwrap();
+        wav.write(&frames).unwrap();
+        wav.write(&frames).unwrap();
+        wav.finish().unwrap();
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.duration(), 4);
+        assert_eq!(reader.spec().channels, 2);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn player() {
+        let path = std::env::temp_dir().join(format!(
+            "harmony_explorer_player_test_{}.wav",
+            std::process::id()
+        ));
+        let sample = Instrument::CasioPiano.load().unwrap();
+        let (tx, rx) = channel();
+        let (player, error) = Player::start(rx, sample, Output::Wav(path.clone()));
+        assert_eq!(error, None);
+        assert_eq!(player.output, Output::Wav(path.clone()));
+
+        tx.send(Message::NoteOn(LetterOctave(Letter::C, 4)))
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        drop(tx);
+        player.finish();
+
+        // The note was recorded as it was played.
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        assert!(reader.duration() as usize >= FRAMES_PER_BUFFER);
+        assert!(reader.samples::<i16>().any(|s| s.unwrap().abs() > 100));
+        std::fs::remove_file(path).unwrap();
+
+        // Outputs which cannot be opened fall back to the null backend.
+        let (tx, rx) = channel();
+        let sample = Instrument::CasioPiano.load().unwrap();
+        let missing = Output::Wav(PathBuf::from("/nonexistent/session.wav"));
+        let (player, error) = Player::start(rx, sample, missing);
+        assert_eq!(player.output, Output::Null);
+        assert!(error.is_some());
+        drop(tx);
+        player.finish();
+    }
+}
@@ -1,9 +1,21 @@
 extern crate clap;
+extern crate diesel;
 
 use clap::Clap;
 use harmony_explorer as hexp;
 
-use hexp::music_theory::{Chord, LetterOctave};
+use diesel::SqliteConnection;
+use hexp::music_theory::{
+    letter_to_string, pitch_class, transpose_semitones, Accidentals, ChordSymbols, Letter,
+    LetterOctave, NotationStyle, Scale,
+};
+use hexp::pitch_class_set::{pitch_class_char, PitchClassSet};
+use hexp::sampler::{render_to_wav, Instrument};
+use hexp::sequencer::{note_events, Ticks, DEFAULT_TEMPO, PPQ};
+
+/// How long the chord is held for when written to a WAV file: two beats at
+/// the default tempo.
+const WAV_LENGTH: Ticks = 2 * PPQ;
 
 /// Print the notes of a given chord.
 #[derive(Clap)]
@@ -11,14 +23,69 @@ use hexp::music_theory::{Chord, LetterOctave};
 #[clap(author = "Alexandru Tiniuc <tiniuc.alexandru@gmail.com>")]
 struct Opts {
     /// Name of the chord to search for.
-    chord: String,
+    chord: Option<String>,
     /// Octave to append to notes. By default, notes are output without octave number.
     #[clap(short, long, default_value = "3")]
     // TODO: make this optional & print notes without octave
     octave: i32,
+    /// Notate accidentals using flats rather than sharps.
+    #[clap(short, long)]
+    flats: bool,
+    /// Use the ♯ and ♭ symbols for accidentals.
+    #[clap(short, long)]
+    unicode: bool,
+    /// Style of chord symbols used to name chords: pop, jazz or long.
+    #[clap(short, long, default_value = "pop")]
+    symbols: ChordSymbols,
+    /// Inversion of the chord: 1 puts the second note of the chord in the
+    /// bass, 2 the third note and so on.
+    #[clap(short, long, default_value = "0")]
+    inversion: usize,
+    /// Write the sound of the chord to this WAV file.
+    #[clap(short, long)]
+    wav: Option<String>,
+    /// Instrument the chord is played on when written to a WAV file: piano
+    /// or thumb.
+    #[clap(long, default_value = "piano")]
+    instrument: Instrument,
+    #[clap(subcommand)]
+    subcommand: Option<SubCommand>,
+}
+
+#[derive(Clap)]
+enum SubCommand {
+    /// Print the notes of a given scale.
+    Scale(ScaleOpts),
+    /// Name every chord made up of the given notes, lowest note first.
+    Identify(IdentifyOpts),
+    /// Describe the pitch-class set of a chord or of a list of notes.
+    Setclass(SetclassOpts),
+}
+
+#[derive(Clap)]
+struct ScaleOpts {
+    /// Root note of the scale.
+    root: String,
+    /// Name of the scale, e.g. dorian or harmonic minor.
+    #[clap(required = true)]
+    name: Vec<String>,
+}
+
+#[derive(Clap)]
+struct IdentifyOpts {
+    /// Notes of the chord, starting from the bass.
+    #[clap(required = true)]
+    notes: Vec<String>,
+}
+
+#[derive(Clap)]
+struct SetclassOpts {
+    /// A single chord symbol, or two or more notes or pitch classes, e.g.
+    /// C E G or 0 4 7. T and E stand for pitch classes 10 and 11.
+    #[clap(required = true)]
+    notes: Vec<String>,
 }
 
-// TODO: add options for flats/sharps, inversions...
 // TODO: add reading from STDIN
 
 fn main() {
@@ -26,29 +93,188 @@ fn main() {
     use hexp::database::*;
     let db = initialise_database().unwrap();
     hexp::chord_library::populate_database(&db);
+    hexp::scale_library::populate_database(&db);
 
     let opts = Opts::parse();
+    let style = NotationStyle {
+        accidentals: if opts.flats {
+            Accidentals::Flats
+        } else {
+            Accidentals::Sharps
+        },
+        unicode: opts.unicode,
+        symbols: opts.symbols,
+        ..NotationStyle::default()
+    };
+
+    match (&opts.subcommand, &opts.chord) {
+        (Some(SubCommand::Scale(scale)), _) => print_scale(scale, &opts, &style, &db),
+        (Some(SubCommand::Identify(identify)), _) => print_identified(identify, &style, &db),
+        (Some(SubCommand::Setclass(setclass)), _) => print_set_class(setclass, &style, &db),
+        (None, Some(chord)) => print_chord(chord, &opts, &style, &db),
+        (None, None) => eprintln!("No chord given!"),
+    }
+}
 
+fn print_chord(name: &str, opts: &Opts, style: &NotationStyle, db: &SqliteConnection) {
     // Parse chord within `opts.chord` CLI field
-    match hexp::parser::command_chord(&opts.chord) {
-        Ok(("", hexp::parser::Command::Chord(letter, quality))) => {
-            // Look up parsed chord within database
-            match get_quality(&quality, &db) {
-                Some(q) => {
-                    // If found, output to stdout!
-                    let chord = Chord {
-                        root: LetterOctave(letter, opts.octave),
-                        quality: q,
-                    };
-                    println!("{}", chord);
-                }
-                None => {
-                    eprintln!("Could not find chord {}!", opts.chord);
+    match hexp::parser::chord_symbol(name) {
+        // Look up parsed chord within database
+        Ok(("", symbol)) => match symbol.to_chord(opts.octave, db) {
+            // If found, output to stdout!
+            Some(chord) => {
+                let chord = match chord.inverted(opts.inversion) {
+                    Some(chord) => chord,
+                    None => {
+                        eprintln!("{} has no inversion {}!", name, opts.inversion);
+                        return;
+                    }
+                };
+                println!("{}", chord.display(style));
+                if let Some(path) = &opts.wav {
+                    let events = note_events(&chord.notes(), 0, WAV_LENGTH);
+                    if let Err(e) = render_to_wav(path, &events, DEFAULT_TEMPO, opts.instrument) {
+                        eprintln!("Could not write {}: {}", path, e);
+                    }
                 }
-            };
+            }
+            None => {
+                eprintln!("Could not find chord {}!", name);
+            }
+        },
+        _ => {
+            eprintln!("Invalid input!");
         }
+    };
+}
+
+fn print_scale(scale: &ScaleOpts, opts: &Opts, style: &NotationStyle, db: &SqliteConnection) {
+    use hexp::database::*;
+    let name = scale.name.join(" ");
+    match hexp::parser::letter(&scale.root) {
+        Ok(("", letter)) => match get_scale(&name, db) {
+            Some(pattern) => {
+                let scale = Scale {
+                    root: LetterOctave(letter, opts.octave),
+                    pattern,
+                };
+                println!("{}", scale.display(style));
+            }
+            None => {
+                eprintln!("Could not find scale {}!", name);
+            }
+        },
         _ => {
             eprintln!("Invalid input!");
         }
     };
 }
+
+fn print_identified(identify: &IdentifyOpts, style: &NotationStyle, db: &SqliteConnection) {
+    let mut notes = vec![];
+    for note in &identify.notes {
+        match hexp::parser::letter(note) {
+            Ok(("", letter)) => notes.push(letter),
+            _ => {
+                eprintln!("Invalid note {}!", note);
+                return;
+            }
+        }
+    }
+
+    let readings = hexp::identify::identify(&notes, db);
+    if readings.is_empty() {
+        eprintln!("Could not identify chord!");
+    }
+    for reading in readings {
+        println!("{}", reading.chord.symbol(style, db));
+    }
+}
+
+/// Reads a pitch class written as a number from 0 to 11, or as a note. In a
+/// list of numbers, T and E stand for 10 and 11 rather than for notes.
+fn parse_pitch_class(input: &str, numbers: bool) -> Option<i32> {
+    match (input, numbers) {
+        ("T", true) => Some(10),
+        ("E", true) => Some(11),
+        _ => match input.parse::<i32>() {
+            Ok(pc) if (0..12).contains(&pc) => Some(pc),
+            Ok(_) => None,
+            Err(_) => match hexp::parser::letter(input) {
+                Ok(("", letter)) => Some(pitch_class(letter)),
+                _ => None,
+            },
+        },
+    }
+}
+
+fn print_set_class(setclass: &SetclassOpts, style: &NotationStyle, db: &SqliteConnection) {
+    let set = match setclass.notes.as_slice() {
+        [name] => match hexp::parser::chord_symbol(name) {
+            Ok(("", symbol)) => match symbol.to_chord(4, db) {
+                Some(chord) => chord.pitch_class_set(),
+                None => {
+                    eprintln!("Could not find chord {}!", name);
+                    return;
+                }
+            },
+            _ => {
+                eprintln!("Invalid input!");
+                return;
+            }
+        },
+        notes => {
+            let numbers = notes.iter().any(|n| n.parse::<i32>().is_ok());
+            let mut pitch_classes = vec![];
+            for note in notes {
+                match parse_pitch_class(note, numbers) {
+                    Some(pc) => pitch_classes.push(pc),
+                    None => {
+                        eprintln!("Invalid note {}!", note);
+                        return;
+                    }
+                }
+            }
+            PitchClassSet::new(pitch_classes)
+        }
+    };
+
+    let pitch_classes =
+        |pcs: &[i32]| -> String { pcs.iter().map(|pc| pitch_class_char(*pc)).collect() };
+    let normal = set.normal_form();
+    let names: Vec<String> = normal
+        .iter()
+        .map(|pc| {
+            letter_to_string(
+                transpose_semitones(LetterOctave(Letter::C, 4), *pc).letter(),
+                style,
+            )
+        })
+        .collect();
+    let vector: String = set
+        .interval_vector()
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+    let forte = |set: &PitchClassSet| set.forte_number().unwrap_or_else(|| "-".to_owned());
+
+    println!("Pitch classes:   {}", set);
+    println!(
+        "Normal form:     [{}] ({})",
+        pitch_classes(&normal),
+        names.join(" ")
+    );
+    println!("Prime form:      ({})", pitch_classes(&set.prime_form()));
+    println!("Forte number:    {}", forte(&set));
+    println!("Interval vector: <{}>", vector);
+    let complement = set.complement();
+    println!("Complement:      {} {}", complement, forte(&complement));
+    match set.z_partner() {
+        Some(partner) => println!(
+            "Z-related to:    ({}) {}",
+            pitch_classes(partner.pitch_classes()),
+            forte(&partner)
+        ),
+        None => println!("Z-related to:    none"),
+    }
+}
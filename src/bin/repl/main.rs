@@ -1,98 +1,166 @@
+extern crate clap;
 extern crate diesel;
 extern crate diesel_migrations;
 extern crate nom;
 
-extern crate dasp;
 extern crate enum_primitive_derive;
-extern crate find_folder; // For easily finding the assets folder.
 extern crate harmony_explorer;
 extern crate num_traits;
 extern crate pitch_calc as pitch; // To work with musical notes.
-extern crate portaudio as pa; // For audio I/O
-extern crate rustyline; // To convert portaudio sample buffers to frames.
-                        // extern crate sampler;
+extern crate rustyline;
 
+use clap::Clap;
 use diesel::SqliteConnection;
+use std::collections::HashMap;
 use std::error::Error;
-use std::sync::mpsc;
-// use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use rustyline::error::ReadlineError;
-use rustyline::Editor;
-
-use harmony_explorer::parser::{parse_command, Command};
-use harmony_explorer::{chord_library, database, music_theory, sequencer};
-// use sampler::Sampler;
-
-/*
-const CHANNELS: i32 = 2;
-const SAMPLE_RATE: f64 = 44_100.0;
-const FRAMES_PER_BUFFER: u32 = 1024;
-//const THUMB_PIANO: &'static str = "thumbpiano A#3.wav";
-const CASIO_PIANO: &'static str = "Casio Piano C5.wav";
-const NOTE_VELOCITY: f32 = 0.6;
-const CHORD_LENGTH: Duration = Duration::from_millis(1000);
-*/
+use rustyline::{Config, Editor, OutputStreamType};
 
-fn main() -> Result<(), Box<dyn Error>> {
-    // Initialise audio plumbing and sampler.
-    /*
+use harmony_explorer::audio::{Output, Player};
+use harmony_explorer::parser::{parse_command, ChordSymbol, Command};
+use harmony_explorer::sampler::{Instrument, Sample};
+use harmony_explorer::sequencer::{Sequencer, Ticks, DEFAULT_TEMPO, PPQ};
+use harmony_explorer::{
+    analysis, chord_library, database, function, identify, music_theory, neo_riemannian,
+    progression, sampler, scale_library, sequencer, substitution, voice_leading, voicing,
+};
+
+/// Chords played on their own last for two beats.
+const CHORD_LENGTH: Ticks = 2 * PPQ;
+/// The notes of a scale are played one after another, half a beat each.
+const SCALE_NOTE_LENGTH: Ticks = PPQ / 2;
+const KEYS_LISTED: usize = 3;
+/// Settings read when the REPL starts, one `name = value` per line. Options
+/// given on the command line take precedence.
+const CONFIG_FILE: &str = ".music_repl_config";
 
-    // We'll create a sample map that maps a single sample to the entire note range.
-    let assets = find_folder::Search::ParentsThenKids(5, 5).for_folder("assets")?;
-    let sample = sampler::Sample::from_wav_file(assets.join(CASIO_PIANO), SAMPLE_RATE)?;
-    let sample_map = sampler::Map::from_single_sample(sample);
-    // Create atomic RC pointer to a mutex protecting the polyphonic sampler
+/// True when the standard output carries audio, in which case everything
+/// the REPL prints goes to the standard error instead.
+static TEXT_TO_STDERR: AtomicBool = AtomicBool::new(false);
 
-    let mut sampler = Sampler::poly((), sample_map).num_voices(12).release(20.0);
-    // Initialise PortAudio and create an output stream.
-    let pa = pa::PortAudio::new()?;
-    let settings =
-        pa.default_output_stream_settings::<f32>(CHANNELS, SAMPLE_RATE, FRAMES_PER_BUFFER)?;
+/// Prints a line of the REPL's output, keeping it out of the way of any
+/// audio written to the standard output.
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if TEXT_TO_STDERR.load(Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
 
-    let (tx, rx) = sequencer::start();
+/// Explore chords, scales and progressions, and hear them played.
+#[derive(Clap)]
+#[clap(name = "Harmony Explorer REPL")]
+#[clap(author = "Alexandru Tiniuc <tiniuc.alexandru@gmail.com>")]
+struct Opts {
+    /// Where the sound goes: portaudio, wav:FILE to record the session, pcm
+    /// to write raw 16-bit stereo samples at 44.1kHz to the standard output
+    /// (e.g. to pipe into `aplay -f cd`), or null for no sound.
+    #[clap(short, long)]
+    audio: Option<Output>,
+    /// Instrument the notes are played on: piano or thumb.
+    #[clap(short, long)]
+    instrument: Option<Instrument>,
+}
 
-    // Callback is frequently called by PortAudio to fill the audio buffer with samples,
-    // which generates sound. Do not do expensive or blocking things in this function!
-    let callback = move |pa::OutputStreamCallbackArgs { buffer, .. }| {
-        for m in rx.try_iter() {
-            match m {
-                sequencer::Message::NoteOn(n) => sampler.note_on(n.to_hz(), NOTE_VELOCITY),
-                sequencer::Message::NoteOff(n) => sampler.note_off(n.to_hz()),
-                sequencer::Message::Stop => sampler.stop(),
+/// Reads the settings in the config file, if there is one. Blank lines and
+/// lines starting with # are skipped.
+fn read_config() -> HashMap<String, String> {
+    let text = std::fs::read_to_string(CONFIG_FILE).unwrap_or_default();
+    let mut config = HashMap::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once('=') {
+            Some((name, value)) => {
+                config.insert(name.trim().to_owned(), value.trim().to_owned());
             }
+            None => eprintln!("Ignoring line of {}: {}", CONFIG_FILE, line),
         }
+    }
+    config
+}
 
-        let buffer: &mut [[f32; CHANNELS as usize]] =
-            sample::slice::to_frame_slice_mut(buffer).unwrap();
-        sample::slice::equilibrium(buffer);
+/// Returns the setting given on the command line, or else the one in the
+/// config file, or else the default.
+fn setting<T: std::str::FromStr<Err = String> + Default>(
+    option: Option<T>,
+    name: &str,
+    config: &HashMap<String, String>,
+) -> T {
+    option
+        .or_else(|| {
+            let value = config.get(name)?;
+            value
+                .parse()
+                .map_err(|e| eprintln!("Ignoring {} in {}: {}", name, CONFIG_FILE, e))
+                .ok()
+        })
+        .unwrap_or_default()
+}
 
-        sampler.fill_slice(buffer, SAMPLE_RATE);
+fn main() -> Result<(), Box<dyn Error>> {
+    let opts = Opts::parse();
+    let config = read_config();
+    let output: Output = setting(opts.audio, "audio", &config);
+    let instrument: Instrument = setting(opts.instrument, "instrument", &config);
+    if output == Output::Pcm {
+        TEXT_TO_STDERR.store(true, Ordering::Relaxed);
+    }
 
-        pa::Continue
+    // Initialise audio plumbing and sampler. Without a sound card or the
+    // samples, the REPL carries on without sound.
+    let (tx, rx) = sequencer::start(DEFAULT_TEMPO);
+    let (sample, output) = match instrument.load() {
+        Ok(sample) => (sample, output),
+        Err(e) => {
+            say!("Could not load the {:?} sample: {}", instrument, e);
+            (
+                Sample::new(vec![], sampler::SAMPLE_RATE, instrument.note()),
+                Output::Null,
+            )
+        }
     };
+    let requested = output.clone();
+    let (player, error) = Player::start(rx, sample, output);
+    if let Some(e) = error {
+        say!("Could not open {} audio output: {}", requested, e);
+    }
+    if player.output == Output::Null {
+        say!("Sound is turned off.");
+    }
 
-    let mut stream = pa.open_non_blocking_stream(settings, callback)?;
-    stream.start()?;
-    */
-    // Audio initialisation is complete. Start processing keyboard input.
-
-    // Duplicate definition, use one above when adding sound back in
-    let (tx, _) = sequencer::start();
-
-    let mut rl = Editor::<()>::new();
-    if let Err(_) = rl.load_history(".music_repl_history") {
+    let config = Config::builder()
+        .output_stream(if TEXT_TO_STDERR.load(Ordering::Relaxed) {
+            OutputStreamType::Stderr
+        } else {
+            OutputStreamType::Stdout
+        })
+        .build();
+    let mut rl = Editor::<()>::with_config(config);
+    if rl.load_history(".music_repl_history").is_err() {
         // No previous history - that's okay!
     }
 
     // In-memory SQLite database of chords
     let db = database::initialise_database().unwrap();
     chord_library::populate_database(&db);
+    scale_library::populate_database(&db);
 
     // The last non-empty command is stored here, to be executed again
     // based on user input.
     let mut last_command: Option<Command> = None;
 
+    let mut session = Session {
+        instrument,
+        ..Session::default()
+    };
+
     loop {
         let readline = rl.readline("♪♪♪ ");
         match readline {
@@ -102,37 +170,74 @@ fn main() -> Result<(), Box<dyn Error>> {
                     Ok(("", command)) => {
                         // Act based on the received command, and save it if it
                         // is not empty.
-                        execute(&command, &last_command, &tx, &db);
+                        execute(&command, &last_command, &mut session, &tx, &db);
                         if command != Command::EmptyString {
                             last_command = Some(command);
                         }
                     }
                     Ok((remaining, _)) => {
                         // Should not get here, the parser should consume all input
-                        println!("Could not process input: {}", remaining);
+                        say!("Could not process input: {}", remaining);
                     }
-                    Err(e) => println!("Error encountered while parsing command: {:?}", e),
+                    Err(e) => say!("Error encountered while parsing command: {:?}", e),
                 }
             }
             Err(ReadlineError::Interrupted) => {
-                println!("CTRL-C, exiting...");
+                say!("CTRL-C, exiting...");
                 break;
             }
             Err(ReadlineError::Eof) => {
-                println!("CTRL-D, exiting...");
+                say!("CTRL-D, exiting...");
                 break;
             }
             Err(err) => {
-                println!("Input Error: {:?}", err);
+                say!("Input Error: {:?}", err);
                 break;
             }
         }
     }
     rl.save_history(".music_repl_history").unwrap();
-    //stream.close()?;
+
+    // Stopping the sequencer lets the audio output finish, e.g. so that a
+    // recording is written out in full.
+    drop(tx);
+    player.finish();
     Ok(())
 }
 
+/// Settings which last for the whole REPL session and can be changed by
+/// commands.
+struct Session {
+    /// How notes and chords are printed.
+    notation: music_theory::NotationStyle,
+    /// How the notes of chords are arranged when played.
+    voicing: voicing::Voicing,
+    /// The key that roman numerals and Nashville numbers are read in.
+    key: Option<music_theory::Key>,
+    /// The time signature that progressions are read in.
+    time: progression::TimeSignature,
+    /// The speed at which progressions are played, in beats per minute.
+    tempo: u32,
+    /// The chords of the substitutions listed last, which can be auditioned.
+    substitutions: Vec<Vec<music_theory::Chord>>,
+    /// The instrument that notes are played and rendered on.
+    instrument: Instrument,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            notation: Default::default(),
+            voicing: Default::default(),
+            key: None,
+            time: Default::default(),
+            tempo: DEFAULT_TEMPO,
+            substitutions: vec![],
+            instrument: Default::default(),
+        }
+    }
+}
+
 // Ideally this function should be as small as possible -
 // all the work should be done in the functional core,
 // the command parser. All this function must do is
@@ -140,73 +245,691 @@ fn main() -> Result<(), Box<dyn Error>> {
 fn execute(
     command: &Command,
     last_command: &Option<Command>,
-    tx: &mpsc::Sender<sequencer::Event>,
+    session: &mut Session,
+    tx: &Sequencer,
     db: &SqliteConnection,
 ) {
     use music_theory::*;
+    // Whatever is still playing is cut off by the next thing to play.
+    if plays_notes(command) {
+        tx.clear();
+    }
     match command {
         // Look up the chord quality in the database, play it and
         // print its notes.
-        Command::Chord(letter, quality) => {
-            use database::*;
-            match get_quality(&quality, &db) {
-                Some(q) => {
-                    let chord = Chord {
-                        root: LetterOctave(*letter, 4),
-                        quality: q,
-                    };
-
-                    /*
-                    chord
-                        .notes()
-                        .into_iter()
-                        .map(|n| {
-                            (
-                                sequencer::Message::NoteOn(n),
-                                sequencer::Message::NoteOff(n),
-                            )
-                        })
-                        .for_each(|(on, off)| {
-                            let zero = Duration::from_millis(0);
-                            tx.send(sequencer::Event { msg: on, del: zero }).unwrap();
-                            tx.send(sequencer::Event {
-                                msg: off,
-                                del: CHORD_LENGTH,
-                            })
-                            .unwrap();
-                        });
-                    */
-
-                    println!("Playing {}", chord);
+        Command::Chord(symbol) => match symbol.to_chord(4, db) {
+            Some(chord) => play_chord(&chord, session, tx),
+            None => {
+                say!("Could not find chord!");
+            }
+        },
+
+        // Play the chord with another of its notes in the bass.
+        Command::Inversion(inversion, symbol) => match symbol.to_chord(4, db) {
+            Some(chord) => match chord.inverted(*inversion) {
+                Some(chord) => {
+                    say!("{}", chord.symbol(&session.notation, db));
+                    play_chord(&chord, session, tx);
                 }
-                None => {
-                    println!("Could not find chord!");
+                None => say!(
+                    "{} has no inversion {}!",
+                    chord.symbol(&session.notation, db),
+                    inversion
+                ),
+            },
+            None => {
+                say!("Could not find chord!");
+            }
+        },
+
+        // Control whatever is playing.
+        Command::Pause => {
+            tx.pause();
+            say!("Paused.");
+        }
+        Command::Resume => {
+            tx.resume();
+            say!("Resumed.");
+        }
+        Command::Stop => {
+            tx.panic();
+            say!("Stopped.");
+        }
+
+        // Look up the scale in the database, play it upwards and print its
+        // notes.
+        Command::Scale(letter, name) => match database::get_scale(name, db) {
+            Some(pattern) => {
+                let scale = Scale {
+                    root: LetterOctave(*letter, 4),
+                    pattern,
+                };
+                for (i, note) in scale.notes().iter().enumerate() {
+                    let start = SCALE_NOTE_LENGTH * i as Ticks;
+                    play_notes(&[*note], start, SCALE_NOTE_LENGTH, tx);
+                }
+                say!("Playing {}", scale.display(&session.notation));
+            }
+            None => {
+                say!("Could not find scale!");
+            }
+        },
+
+        // Build a chord on every degree of the scale, and print their names
+        // along with their roman numerals.
+        Command::Diatonic(letter, name, size) => match database::get_scale(name, db) {
+            Some(pattern) => {
+                let scale = Scale {
+                    root: LetterOctave(*letter, 4),
+                    pattern,
+                };
+                let chords = match scale.harmonize(*size) {
+                    Some(chords) => chords,
+                    None => {
+                        say!("Chords can only be built on scales of seven notes!");
+                        return;
+                    }
+                };
+                let numerals: Vec<String> = chords
+                    .iter()
+                    .zip(1..)
+                    .map(|(c, degree)| analysis::roman_numeral(degree, &c.quality))
+                    .collect();
+                let names: Vec<String> = chords
+                    .iter()
+                    .map(|c| c.symbol(&session.notation, db))
+                    .collect();
+                let width = names
+                    .iter()
+                    .chain(numerals.iter())
+                    .map(|n| n.chars().count())
+                    .max()
+                    .unwrap_or(0)
+                    + 2;
+
+                for row in &[numerals, names] {
+                    let line: String = row.iter().map(|n| format!("{:1$}", n, width)).collect();
+                    say!("{}", line.trim_end());
                 }
             }
+            None => {
+                say!("Could not find scale!");
+            }
+        },
+
+        // Name every chord that could be made up of the notes.
+        Command::Identify(notes) => {
+            let readings = identify::identify(notes, db);
+            if readings.is_empty() {
+                say!("Could not identify chord!");
+            }
+            for reading in readings {
+                say!("{}", reading.chord.symbol(&session.notation, db));
+            }
         }
+
         // Re-do the last command.
         Command::EmptyString => match last_command {
             Some(Command::EmptyString) => (),
-            Some(c) => execute(&c, &None, tx, db),
+            Some(c) => execute(c, &None, session, tx, db),
             None => (),
         },
 
         Command::Flats => {
-            set_use_flats(true);
-            println!("Notating accidentals using flats.");
+            session.notation.accidentals = Accidentals::Flats;
+            say!("Notating accidentals using flats.");
+        }
+
+        // Voice the chords so that they move smoothly from one to the
+        // next, play them one after the other and print each voice's path.
+        Command::Lead(symbols) => {
+            let chords = match to_chords(symbols, session, db) {
+                Some(chords) => chords,
+                None => return,
+            };
+
+            let ranges = voice_leading::SATB;
+            match voice_leading::lead(&chords, &ranges) {
+                Some(leading) => {
+                    for (i, notes) in leading.notes().into_iter().enumerate() {
+                        play_notes(&notes, CHORD_LENGTH * i as Ticks, CHORD_LENGTH, tx);
+                    }
+
+                    let movements = leading.movements();
+                    for (voice, range) in ranges.iter().enumerate().rev() {
+                        let path: Vec<String> = leading
+                            .voice(voice)
+                            .iter()
+                            .map(|(n, o)| session.notation.note_octave_to_string(*n, *o))
+                            .collect();
+                        let moved: i32 = movements[voice].iter().map(|m| m.abs()).sum();
+                        say!("{:8} {}  ({} semitones)", range.name, path.join(" "), moved);
+                    }
+                    say!("Total movement: {} semitones", leading.total_movement());
+                }
+                None => say!("Could not voice the chords within the voices' ranges!"),
+            }
+        }
+
+        Command::Voicing(voicing) => {
+            session.voicing = *voicing;
+            say!("Playing chords in {} voicing.", voicing);
         }
 
         Command::Sharps => {
-            set_use_flats(false);
-            println!("Notating accidentals using sharps.");
+            session.notation.accidentals = Accidentals::Sharps;
+            say!("Notating accidentals using sharps.");
+        }
+
+        // Transpose the chord, spelling the new root with the session's
+        // accidentals, then print its name and play it.
+        Command::Transpose(distance, symbol) => match symbol.to_chord(4, db) {
+            Some(chord) => {
+                let transposed = respelled(&chord.transposed_semitones(*distance), session);
+
+                say!("{}", transposed.symbol(&session.notation, db));
+                play_chord(&transposed, session, tx);
+            }
+            None => {
+                say!("Could not find chord!");
+            }
+        },
+
+        Command::Symbols(symbols) => {
+            session.notation.symbols = *symbols;
+            say!("Writing chord symbols in {} style.", symbols.name());
+        }
+
+        // Print every chord above its roman numeral in the key, marking
+        // the ones that do not belong to it.
+        Command::Analyze(key, symbols) => {
+            let chords = match to_chords(symbols, session, db) {
+                Some(chords) => chords,
+                None => return,
+            };
+            let analyses = analysis::analyze(key, &chords);
+
+            let names: Vec<String> = chords
+                .iter()
+                .map(|c| c.symbol(&session.notation, db))
+                .collect();
+            let numerals: Vec<String> = analyses.iter().map(|a| a.numeral.clone()).collect();
+            let relations: Vec<String> = analyses
+                .iter()
+                .map(|a| match a.relation {
+                    analysis::Relation::Diatonic => "",
+                    analysis::Relation::Secondary => "secondary",
+                    analysis::Relation::Borrowed => "borrowed",
+                    analysis::Relation::Chromatic => "chromatic",
+                })
+                .map(str::to_owned)
+                .collect();
+            let width = names
+                .iter()
+                .chain(numerals.iter())
+                .chain(relations.iter())
+                .map(|n| n.chars().count())
+                .max()
+                .unwrap_or(0)
+                + 2;
+
+            for row in &[names, numerals, relations] {
+                let line: String = row.iter().map(|n| format!("{:1$}", n, width)).collect();
+                if !line.trim().is_empty() {
+                    say!("{}", line.trim_end());
+                }
+            }
+        }
+
+        Command::Key(key) => {
+            say!("Reading chords in {}.", key_name(key, session));
+            session.key = Some(key.clone());
+        }
+
+        // Work out the chords in the session's key, then print their names
+        // and play them one after the other.
+        Command::Degrees(symbols) => {
+            let key = match &session.key {
+                Some(key) => key,
+                None => {
+                    say!("No key has been set! Set one with e.g. key Bb.");
+                    return;
+                }
+            };
+            let mut chords = vec![];
+            for symbol in symbols {
+                match symbol.to_chord(key, 4, db) {
+                    Some(chord) => chords.push(chord),
+                    None => {
+                        say!("Could not find chord {}!", symbol.quality);
+                        return;
+                    }
+                }
+            }
+
+            let names: Vec<String> = chords
+                .iter()
+                .map(|c| c.symbol(&session.notation, db))
+                .collect();
+            say!("{}", names.join(" "));
+            for (i, chord) in chords.iter().enumerate() {
+                let start = CHORD_LENGTH * i as Ticks;
+                play_notes(&session.voicing.notes(chord), start, CHORD_LENGTH, tx);
+            }
+        }
+
+        Command::Progression(bars) => {
+            if let Some(progression) = to_progression(bars, session, db) {
+                play_progression(&progression, session, tx, db);
+            }
+        }
+
+        // Transpose every chord of the progression, spelling the roots with
+        // the session's accidentals.
+        Command::TransposeProgression(distance, bars) => {
+            if let Some(progression) = to_progression(bars, session, db) {
+                let mut transposed = progression.transposed(*distance);
+                for (chord, _) in transposed.chords.iter_mut() {
+                    *chord = respelled(chord, session);
+                }
+                play_progression(&transposed, session, tx, db);
+            }
+        }
+
+        Command::Time(time) => {
+            session.time = *time;
+            say!("Reading progressions in {} time.", time);
+        }
+
+        // Change the tempo straight away, speeding up or slowing down
+        // anything still playing.
+        Command::Tempo(tempo) => {
+            session.tempo = *tempo;
+            tx.send(sequencer::Event {
+                msg: sequencer::Message::Tempo(*tempo),
+                del: 0,
+            });
+            say!("Playing at {} beats per minute.", tempo);
+        }
+
+        // List the most likely keys of the progression, and the points
+        // where it changes key.
+        Command::Keys(bars) => {
+            if let Some(progression) = to_progression(bars, session, db) {
+                for estimate in progression.detect_keys().iter().take(KEYS_LISTED) {
+                    say!(
+                        "{:12} {:3.0}%",
+                        key_name(&estimate.key, session),
+                        estimate.confidence * 100.0
+                    );
+                }
+
+                let chords: Vec<music_theory::Chord> =
+                    progression.chords.iter().map(|(c, _)| c.clone()).collect();
+                for modulation in analysis::modulations(&chords).iter().skip(1) {
+                    say!(
+                        "Modulates to {} at chord {} ({}).",
+                        key_name(&modulation.key, session),
+                        modulation.index + 1,
+                        chords[modulation.index].symbol(&session.notation, db)
+                    );
+                }
+            }
+        }
+
+        // Print every chord above its harmonic function, and bracket the
+        // cadences underneath.
+        Command::Functions(key, symbols) => {
+            let chords = match to_chords(symbols, session, db) {
+                Some(chords) => chords,
+                None => return,
+            };
+            let labelled = function::label(key, &chords);
+
+            let names: Vec<String> = chords
+                .iter()
+                .map(|c| c.symbol(&session.notation, db))
+                .collect();
+            let functions: Vec<&str> = labelled
+                .functions
+                .iter()
+                .map(|f| f.map(|f| f.abbreviation()).unwrap_or("?"))
+                .collect();
+            let labels: Vec<String> = labelled
+                .cadences
+                .iter()
+                .map(|c| c.kind.to_string())
+                .collect();
+            // Wide enough for a bracket across two columns to fit its label.
+            let width = names
+                .iter()
+                .map(|n| n.chars().count() + 2)
+                .chain(labels.iter().map(|l| (l.chars().count() + 7) / 2 + 1))
+                .max()
+                .unwrap_or(0);
+
+            let row = |cells: Vec<String>| -> String {
+                let line: String = cells.iter().map(|n| format!("{:1$}", n, width)).collect();
+                line.trim_end().to_owned()
+            };
+            say!("{}", row(names));
+            say!("{}", row(functions.iter().map(|f| f.to_string()).collect()));
+
+            // Cadences which overlap go on separate lines.
+            let mut lines: Vec<String> = vec![];
+            for (cadence, label) in labelled.cadences.iter().zip(&labels) {
+                let start = (cadence.end - 1) * width;
+                let dashes = 2 * width - 2 - 4 - label.chars().count();
+                let bracket = format!(
+                    "└{} {} {}┘",
+                    "─".repeat(dashes / 2),
+                    label,
+                    "─".repeat(dashes - dashes / 2)
+                );
+                match lines.iter_mut().find(|l| l.chars().count() <= start) {
+                    Some(line) => {
+                        let padding = start - line.chars().count();
+                        line.push_str(&" ".repeat(padding));
+                        line.push_str(&bracket);
+                    }
+                    None => lines.push(" ".repeat(start) + &bracket),
+                }
+            }
+            for line in lines {
+                say!("{}", line);
+            }
+        }
+
+        // List the substitutions, most strongly suggested first, and keep
+        // them so that they can be auditioned.
+        Command::Substitutions(symbol, key) => match symbol.to_chord(4, db) {
+            Some(chord) => {
+                let subs = substitution::substitutions(key, &chord);
+                for (i, sub) in subs.iter().enumerate() {
+                    let names: Vec<String> = sub
+                        .chords
+                        .iter()
+                        .map(|c| c.symbol(&session.notation, db))
+                        .collect();
+                    say!(
+                        "{:2}. {:14} {}: {}",
+                        i + 1,
+                        names.join(" "),
+                        sub.kind,
+                        sub.rationale
+                    );
+                }
+                session.substitutions = subs.into_iter().map(|s| s.chords).collect();
+            }
+            None => {
+                say!("Could not find chord!");
+            }
+        },
+
+        Command::Audition(number) => match session.substitutions.get(number.wrapping_sub(1)) {
+            Some(chords) => {
+                for (i, chord) in chords.iter().enumerate() {
+                    let start = CHORD_LENGTH * i as Ticks;
+                    play_notes(&session.voicing.notes(chord), start, CHORD_LENGTH, tx);
+                }
+                let names: Vec<String> = chords
+                    .iter()
+                    .map(|c| c.symbol(&session.notation, db))
+                    .collect();
+                say!("Playing {}", names.join(" "));
+            }
+            None => say!("There is no substitution number {}!", number),
+        },
+
+        // Print and play every step of the transform.
+        Command::NeoRiemannian(symbol, transforms) => {
+            match symbol.to_chord(4, db).and_then(|c| {
+                let steps = c.transformed_by(transforms)?;
+                Some((c, steps))
+            }) {
+                Some((chord, steps)) => {
+                    let chords: Vec<music_theory::Chord> =
+                        std::iter::once(chord).chain(steps).collect();
+                    play_steps(&chords, transforms, session, tx, db);
+                }
+                None => say!("Neo-Riemannian transforms only apply to major and minor triads!"),
+            }
+        }
+
+        // Find the fewest transforms from one triad to the other, then
+        // print and play every step.
+        Command::NeoRiemannianPath(from, to) => {
+            let path = match (from.to_chord(4, db), to.to_chord(4, db)) {
+                (Some(from), Some(to)) => {
+                    neo_riemannian::shortest_path(&from, &to).map(|path| (from, path))
+                }
+                _ => None,
+            };
+            match path {
+                Some((chord, path)) => {
+                    let steps = chord.transformed_by(&path).unwrap_or_default();
+                    let chords: Vec<music_theory::Chord> =
+                        std::iter::once(chord).chain(steps).collect();
+                    play_steps(&chords, &path, session, tx, db);
+                }
+                None => say!("Neo-Riemannian transforms only apply to major and minor triads!"),
+            }
         }
 
-        Command::Transpose(distance, letter, quality) => {
-            let new_letter = *letter + *distance;
-            let new_command = Command::Chord(new_letter, quality.to_string());
+        // Run the command, writing the notes it plays to a WAV file rather
+        // than playing them.
+        Command::Render(path, command) => {
+            // Rendering the last command again writes it to the new file.
+            let command = match (command.as_ref(), last_command) {
+                (Command::EmptyString, Some(Command::Render(_, last))) => last.as_ref(),
+                _ => command.as_ref(),
+            };
+            let (render_tx, render_rx) = Sequencer::recorder();
+            execute(command, last_command, session, &render_tx, db);
+            let events: Vec<sequencer::Event> = render_rx.try_iter().collect();
+            if events.is_empty() {
+                say!("Nothing to render!");
+                return;
+            }
+            match sampler::render_to_wav(path, &events, session.tempo, session.instrument) {
+                Ok(()) => say!("Rendered to {}", path),
+                Err(e) => say!("Could not render to {}: {}", path, e),
+            }
+        }
+
+        // Print the negative of every chord below it, then play the
+        // original chords followed by their negatives.
+        Command::Negative(axis, symbols) => {
+            let chords = match to_chords(symbols, session, db) {
+                Some(chords) => chords,
+                None => return,
+            };
+            let negatives: Option<Vec<music_theory::Chord>> =
+                chords.iter().map(|c| c.negative(axis, db)).collect();
+            let negatives = match negatives {
+                Some(negatives) => negatives,
+                None => {
+                    say!("Could not name the negative of every chord!");
+                    return;
+                }
+            };
 
-            println!("{}{}", letter_to_string(new_letter), quality.to_string());
-            execute(&new_command, last_command, tx, db);
+            let names = |chords: &[music_theory::Chord]| -> Vec<String> {
+                chords
+                    .iter()
+                    .map(|c| c.symbol(&session.notation, db))
+                    .collect()
+            };
+            let rows = [names(&chords), names(&negatives)];
+            let width = rows
+                .iter()
+                .flatten()
+                .map(|n| n.chars().count())
+                .max()
+                .unwrap_or(0)
+                + 2;
+            for row in &rows {
+                let line: String = row.iter().map(|n| format!("{:1$}", n, width)).collect();
+                say!("{}", line.trim_end());
+            }
+
+            for (i, chord) in chords.iter().chain(&negatives).enumerate() {
+                let start = CHORD_LENGTH * i as Ticks;
+                play_notes(&session.voicing.notes(chord), start, CHORD_LENGTH, tx);
+            }
         }
     };
 }
+
+/// Prints the chords with the transform leading to each, e.g. "C -L-> Em",
+/// and plays them one after the other.
+fn play_steps(
+    chords: &[music_theory::Chord],
+    transforms: &[neo_riemannian::Transform],
+    session: &Session,
+    tx: &Sequencer,
+    db: &SqliteConnection,
+) {
+    let mut line = chords[0].symbol(&session.notation, db);
+    for (chord, transform) in chords[1..].iter().zip(transforms) {
+        line.push_str(&format!(
+            " -{}-> {}",
+            transform,
+            chord.symbol(&session.notation, db)
+        ));
+    }
+    say!("{}", line);
+    for (i, chord) in chords.iter().enumerate() {
+        let start = CHORD_LENGTH * i as Ticks;
+        play_notes(&session.voicing.notes(chord), start, CHORD_LENGTH, tx);
+    }
+}
+
+/// Looks up the chords of every bar and shares the bars between them in the
+/// session's time signature, printing an error and returning `None` if that
+/// cannot be done.
+fn to_progression(
+    bars: &[Vec<Option<ChordSymbol>>],
+    session: &Session,
+    db: &SqliteConnection,
+) -> Option<progression::Progression> {
+    let mut chords = vec![];
+    for bar in bars {
+        let symbols: Vec<ChordSymbol> = bar.iter().flatten().cloned().collect();
+        let mut found = to_chords(&symbols, session, db)?.into_iter();
+        chords.push(
+            bar.iter()
+                .map(|slot| slot.as_ref().and_then(|_| found.next()))
+                .collect(),
+        );
+    }
+    let progression = progression::Progression::from_bars(&chords, session.time);
+    if progression.is_none() {
+        say!(
+            "Could not share the bars evenly between their chords in {}!",
+            session.time
+        );
+    }
+    progression
+}
+
+/// Prints the progression as a lead sheet and plays it at the session's
+/// tempo.
+fn play_progression(
+    progression: &progression::Progression,
+    session: &Session,
+    tx: &Sequencer,
+    db: &SqliteConnection,
+) {
+    say!("{}", progression.lead_sheet(&session.notation, db));
+    for ((chord, beats), start) in progression.chords.iter().zip(progression.starts()) {
+        let notes = session.voicing.notes(chord);
+        play_notes(&notes, PPQ * start as Ticks, PPQ * *beats as Ticks, tx);
+    }
+}
+
+/// Spells the root and bass of the chord with the session's accidentals.
+fn respelled(chord: &music_theory::Chord, session: &Session) -> music_theory::Chord {
+    let root = chord.root;
+    music_theory::Chord {
+        root: music_theory::LetterOctave(
+            session.notation.respell_letter(root.letter()),
+            root.octave(),
+        ),
+        bass: chord.bass.map(|b| session.notation.respell_letter(b)),
+        ..chord.clone()
+    }
+}
+
+/// Writes down a key, e.g. "Bb major".
+fn key_name(key: &music_theory::Key, session: &Session) -> String {
+    format!(
+        "{} {}",
+        session
+            .notation
+            .note_to_string(music_theory::SpelledNote::from_letter(key.tonic())),
+        if key.is_minor() { "minor" } else { "major" }
+    )
+}
+
+/// Looks up every chord symbol, printing an error and returning `None` if
+/// one of them cannot be found.
+fn to_chords(
+    symbols: &[ChordSymbol],
+    session: &Session,
+    db: &SqliteConnection,
+) -> Option<Vec<music_theory::Chord>> {
+    let mut chords = vec![];
+    for symbol in symbols {
+        match symbol.to_chord(4, db) {
+            Some(chord) => chords.push(chord),
+            None => {
+                say!(
+                    "Could not find chord {}{}!",
+                    music_theory::letter_to_string(symbol.root, &session.notation),
+                    symbol.quality
+                );
+                return None;
+            }
+        }
+    }
+    Some(chords)
+}
+
+/// Returns true if the command plays notes, rather than only printing
+/// something or changing a setting.
+fn plays_notes(command: &Command) -> bool {
+    matches!(
+        command,
+        Command::Chord(_)
+            | Command::Scale(_, _)
+            | Command::Inversion(_, _)
+            | Command::Transpose(_, _)
+            | Command::Lead(_)
+            | Command::Degrees(_)
+            | Command::Progression(_)
+            | Command::TransposeProgression(_, _)
+            | Command::Audition(_)
+            | Command::NeoRiemannian(_, _)
+            | Command::NeoRiemannianPath(_, _)
+            | Command::Negative(_, _)
+    )
+}
+
+/// Plays the chord in the session's voicing, and prints the notes played.
+fn play_chord(chord: &music_theory::Chord, session: &Session, tx: &Sequencer) {
+    let notes = session.voicing.notes(chord);
+    play_notes(&notes, 0, CHORD_LENGTH, tx);
+    say!(
+        "Playing {}",
+        session.voicing.display(chord, &session.notation)
+    );
+}
+
+/// Sends the notes to the sequencer, to be played together after `delay`
+/// and held for `length`.
+fn play_notes(notes: &[music_theory::LetterOctave], delay: Ticks, length: Ticks, tx: &Sequencer) {
+    for event in sequencer::note_events(notes, delay, length) {
+        tx.send(event);
+    }
+}
@@ -5,7 +5,7 @@ use diesel::prelude::*;
 use diesel::sqlite::SqliteConnection;
 
 pub fn generate_chords() -> Vec<ChordNote> {
-    use degree_intervals::*;
+    use intervals::*;
     vec![
         // Triads
         ("major", Maj3rd),
@@ -32,35 +32,285 @@ pub fn generate_chords() -> Vec<ChordNote> {
         ("diminished seventh", Min3rd),
         ("diminished seventh", Dim5th),
         ("diminished seventh", Dim7th),
+        ("half-diminished seventh", Min3rd),
+        ("half-diminished seventh", Dim5th),
+        ("half-diminished seventh", Min7th),
+        ("minor major seventh", Min3rd),
+        ("minor major seventh", Per5th),
+        ("minor major seventh", Maj7th),
+        ("augmented major seventh", Maj3rd),
+        ("augmented major seventh", Aug5th),
+        ("augmented major seventh", Maj7th),
+        ("augmented seventh", Maj3rd),
+        ("augmented seventh", Aug5th),
+        ("augmented seventh", Min7th),
+        ("minor sixth", Min3rd),
+        ("minor sixth", Per5th),
+        ("minor sixth", Maj6th),
+        // Pentads
+        ("major ninth", Maj3rd),
+        ("major ninth", Per5th),
+        ("major ninth", Maj7th),
+        ("major ninth", Maj9th),
+        ("dominant ninth", Maj3rd),
+        ("dominant ninth", Per5th),
+        ("dominant ninth", Min7th),
+        ("dominant ninth", Maj9th),
+        ("minor ninth", Min3rd),
+        ("minor ninth", Per5th),
+        ("minor ninth", Min7th),
+        ("minor ninth", Maj9th),
+        ("dominant seventh flat ninth", Maj3rd),
+        ("dominant seventh flat ninth", Per5th),
+        ("dominant seventh flat ninth", Min7th),
+        ("dominant seventh flat ninth", Min9th),
+        ("minor seventh flat ninth", Min3rd),
+        ("minor seventh flat ninth", Per5th),
+        ("minor seventh flat ninth", Min7th),
+        ("minor seventh flat ninth", Min9th),
+        ("half-diminished ninth", Min3rd),
+        ("half-diminished ninth", Dim5th),
+        ("half-diminished ninth", Min7th),
+        ("half-diminished ninth", Maj9th),
+        ("half-diminished flat ninth", Min3rd),
+        ("half-diminished flat ninth", Dim5th),
+        ("half-diminished flat ninth", Min7th),
+        ("half-diminished flat ninth", Min9th),
+        ("minor major ninth", Min3rd),
+        ("minor major ninth", Per5th),
+        ("minor major ninth", Maj7th),
+        ("minor major ninth", Maj9th),
+        ("augmented major ninth", Maj3rd),
+        ("augmented major ninth", Aug5th),
+        ("augmented major ninth", Maj7th),
+        ("augmented major ninth", Maj9th),
+        ("major seventh sharp ninth", Maj3rd),
+        ("major seventh sharp ninth", Per5th),
+        ("major seventh sharp ninth", Maj7th),
+        ("major seventh sharp ninth", Aug9th),
+        ("diminished seventh flat ninth", Min3rd),
+        ("diminished seventh flat ninth", Dim5th),
+        ("diminished seventh flat ninth", Dim7th),
+        ("diminished seventh flat ninth", Min9th),
+        // Suspended & added-note chords
+        ("suspended second", Maj2nd),
+        ("suspended second", Per5th),
+        ("suspended fourth", Per4th),
+        ("suspended fourth", Per5th),
+        ("dominant seventh suspended fourth", Per4th),
+        ("dominant seventh suspended fourth", Per5th),
+        ("dominant seventh suspended fourth", Min7th),
+        ("added ninth", Maj3rd),
+        ("added ninth", Per5th),
+        ("added ninth", Maj9th),
+        ("minor added ninth", Min3rd),
+        ("minor added ninth", Per5th),
+        ("minor added ninth", Maj9th),
+        ("six nine", Maj3rd),
+        ("six nine", Per5th),
+        ("six nine", Maj6th),
+        ("six nine", Maj9th),
+        // Elevenths & thirteenths
+        ("dominant eleventh", Maj3rd),
+        ("dominant eleventh", Per5th),
+        ("dominant eleventh", Min7th),
+        ("dominant eleventh", Maj9th),
+        ("dominant eleventh", Per11th),
+        ("minor eleventh", Min3rd),
+        ("minor eleventh", Per5th),
+        ("minor eleventh", Min7th),
+        ("minor eleventh", Maj9th),
+        ("minor eleventh", Per11th),
+        ("dominant seventh sharp eleventh", Maj3rd),
+        ("dominant seventh sharp eleventh", Per5th),
+        ("dominant seventh sharp eleventh", Min7th),
+        ("dominant seventh sharp eleventh", Aug11th),
+        ("major seventh sharp eleventh", Maj3rd),
+        ("major seventh sharp eleventh", Per5th),
+        ("major seventh sharp eleventh", Maj7th),
+        ("major seventh sharp eleventh", Aug11th),
+        ("dominant thirteenth", Maj3rd),
+        ("dominant thirteenth", Per5th),
+        ("dominant thirteenth", Min7th),
+        ("dominant thirteenth", Maj9th),
+        ("dominant thirteenth", Maj13th),
+        ("major thirteenth", Maj3rd),
+        ("major thirteenth", Per5th),
+        ("major thirteenth", Maj7th),
+        ("major thirteenth", Maj9th),
+        ("major thirteenth", Maj13th),
     ]
     .into_iter()
-    .map(|t| ChordNote::note(t.0, (t.1).0, (t.1).1))
+    .map(|(chord, interval)| ChordNote::note(chord, interval))
     .collect()
 }
 
 pub fn generate_names() -> Vec<ChordName> {
+    use ChordSymbols::*;
+    // The third field marks the style of chord symbols in which the name is
+    // used when writing the chord down, if any.
     vec![
-        ("major", ""),
-        ("major", "maj"),
-        ("minor", "m"),
-        ("minor", "-"),
-        ("diminished", "dim"),
-        ("diminished", "o"),
-        ("diminished", "*"),
-        ("augmented", "+"),
-        ("augmented", "aug"),
-        ("major seventh", "M7"),
-        ("major seventh", "maj7"),
-        ("dominant seventh", "7"),
-        ("minor seventh", "m7"),
-        ("minor seventh", "-7"),
-        ("major sixth", "6"),
-        ("major sixth", "maj6"),
-        ("diminished seventh", "dim7"),
-        ("diminished seventh", "o7"),
+        ("major", "", Some(Pop)),
+        ("major", "maj", None),
+        ("minor", "m", Some(Pop)),
+        ("minor", "-", Some(Jazz)),
+        ("diminished", "dim", Some(Pop)),
+        ("diminished", "o", None),
+        ("diminished", "*", None),
+        ("augmented", "+", Some(Jazz)),
+        ("augmented", "aug", Some(Pop)),
+        ("major seventh", "M7", None),
+        ("major seventh", "maj7", Some(Pop)),
+        ("dominant seventh", "7", Some(Pop)),
+        ("minor seventh", "m7", Some(Pop)),
+        ("minor seventh", "-7", Some(Jazz)),
+        ("major sixth", "6", Some(Pop)),
+        ("major sixth", "maj6", None),
+        ("diminished seventh", "dim7", Some(Pop)),
+        ("diminished seventh", "o7", None),
+        ("half-diminished seventh", "m7b5", Some(Pop)),
+        ("half-diminished seventh", "-7b5", None),
+        ("half-diminished seventh", "ø", None),
+        ("half-diminished seventh", "ø7", Some(Jazz)),
+        ("minor major seventh", "mMaj7", Some(Pop)),
+        ("minor major seventh", "m(maj7)", None),
+        ("minor major seventh", "-maj7", None),
+        ("augmented major seventh", "maj7#5", Some(Pop)),
+        ("augmented major seventh", "+maj7", None),
+        ("augmented seventh", "7#5", Some(Pop)),
+        ("augmented seventh", "+7", Some(Jazz)),
+        ("augmented seventh", "aug7", None),
+        ("minor sixth", "m6", Some(Pop)),
+        ("minor sixth", "-6", Some(Jazz)),
+        ("major ninth", "maj9", Some(Pop)),
+        ("major ninth", "M9", None),
+        ("dominant ninth", "9", Some(Pop)),
+        ("minor ninth", "m9", Some(Pop)),
+        ("minor ninth", "-9", Some(Jazz)),
+        ("dominant seventh flat ninth", "7b9", Some(Pop)),
+        ("minor seventh flat ninth", "m7b9", Some(Pop)),
+        ("minor seventh flat ninth", "-7b9", Some(Jazz)),
+        ("half-diminished ninth", "m9b5", Some(Pop)),
+        ("half-diminished ninth", "ø9", Some(Jazz)),
+        ("half-diminished flat ninth", "m7b5b9", Some(Pop)),
+        ("half-diminished flat ninth", "ø7b9", Some(Jazz)),
+        ("minor major ninth", "mMaj9", Some(Pop)),
+        ("minor major ninth", "m(maj9)", None),
+        ("augmented major ninth", "maj9#5", Some(Pop)),
+        ("major seventh sharp ninth", "maj7#9", Some(Pop)),
+        ("diminished seventh flat ninth", "dim7b9", Some(Pop)),
+        ("suspended second", "sus2", Some(Pop)),
+        ("suspended fourth", "sus4", Some(Pop)),
+        ("suspended fourth", "sus", None),
+        ("dominant seventh suspended fourth", "7sus4", Some(Pop)),
+        ("dominant seventh suspended fourth", "7sus", None),
+        ("added ninth", "add9", Some(Pop)),
+        ("added ninth", "add2", None),
+        ("minor added ninth", "madd9", Some(Pop)),
+        ("minor added ninth", "m(add9)", None),
+        ("six nine", "6/9", Some(Pop)),
+        ("six nine", "69", None),
+        ("dominant eleventh", "11", Some(Pop)),
+        ("minor eleventh", "m11", Some(Pop)),
+        ("minor eleventh", "-11", Some(Jazz)),
+        ("dominant seventh sharp eleventh", "7#11", Some(Pop)),
+        ("major seventh sharp eleventh", "maj7#11", Some(Pop)),
+        ("dominant thirteenth", "13", Some(Pop)),
+        ("major thirteenth", "maj13", Some(Pop)),
+        // Jazz symbols
+        ("diminished", "°", Some(Jazz)),
+        ("major seventh", "Δ7", Some(Jazz)),
+        ("diminished seventh", "°7", Some(Jazz)),
+        ("minor major seventh", "-Δ7", Some(Jazz)),
+        ("augmented major seventh", "+Δ7", Some(Jazz)),
+        ("major ninth", "Δ9", Some(Jazz)),
+        ("minor major ninth", "-Δ9", Some(Jazz)),
+        ("augmented major ninth", "+Δ9", Some(Jazz)),
+        ("major seventh sharp ninth", "Δ7#9", Some(Jazz)),
+        ("diminished seventh flat ninth", "°7b9", Some(Jazz)),
+        ("minor added ninth", "-add9", Some(Jazz)),
+        ("major seventh sharp eleventh", "Δ7#11", Some(Jazz)),
+        ("major thirteenth", "Δ13", Some(Jazz)),
+        // Long form, as the chord would be read out
+        ("major", "major", Some(Long)),
+        ("minor", "minor", Some(Long)),
+        ("diminished", "diminished", Some(Long)),
+        ("augmented", "augmented", Some(Long)),
+        ("major seventh", "major seven", Some(Long)),
+        ("dominant seventh", "seven", Some(Long)),
+        ("minor seventh", "minor seven", Some(Long)),
+        ("major sixth", "six", Some(Long)),
+        ("diminished seventh", "diminished seven", Some(Long)),
+        (
+            "half-diminished seventh",
+            "minor seven flat five",
+            Some(Long),
+        ),
+        ("minor major seventh", "minor major seven", Some(Long)),
+        (
+            "augmented major seventh",
+            "major seven sharp five",
+            Some(Long),
+        ),
+        ("augmented seventh", "seven sharp five", Some(Long)),
+        ("minor sixth", "minor six", Some(Long)),
+        ("major ninth", "major nine", Some(Long)),
+        ("dominant ninth", "nine", Some(Long)),
+        ("minor ninth", "minor nine", Some(Long)),
+        ("dominant seventh flat ninth", "seven flat nine", Some(Long)),
+        (
+            "minor seventh flat ninth",
+            "minor seven flat nine",
+            Some(Long),
+        ),
+        ("half-diminished ninth", "minor nine flat five", Some(Long)),
+        (
+            "half-diminished flat ninth",
+            "minor seven flat five flat nine",
+            Some(Long),
+        ),
+        ("minor major ninth", "minor major nine", Some(Long)),
+        ("augmented major ninth", "major nine sharp five", Some(Long)),
+        (
+            "major seventh sharp ninth",
+            "major seven sharp nine",
+            Some(Long),
+        ),
+        (
+            "diminished seventh flat ninth",
+            "diminished seven flat nine",
+            Some(Long),
+        ),
+        ("suspended second", "sus two", Some(Long)),
+        ("suspended fourth", "sus four", Some(Long)),
+        (
+            "dominant seventh suspended fourth",
+            "seven sus four",
+            Some(Long),
+        ),
+        ("added ninth", "add nine", Some(Long)),
+        ("minor added ninth", "minor add nine", Some(Long)),
+        ("six nine", "six nine", Some(Long)),
+        ("dominant eleventh", "eleven", Some(Long)),
+        ("minor eleventh", "minor eleven", Some(Long)),
+        (
+            "dominant seventh sharp eleventh",
+            "seven sharp eleven",
+            Some(Long),
+        ),
+        (
+            "major seventh sharp eleventh",
+            "major seven sharp eleven",
+            Some(Long),
+        ),
+        ("dominant thirteenth", "thirteen", Some(Long)),
+        ("major thirteenth", "major thirteen", Some(Long)),
     ]
     .into_iter()
-    .map(|t| ChordName::name(t.0, t.1))
+    .map(|(chord, name, style)| match style {
+        Some(style) => ChordName::styled_name(chord, name, style),
+        None => ChordName::name(chord, name),
+    })
     .collect()
 }
 
@@ -10,18 +10,25 @@ embed_migrations!("migrations/");
 #[table_name = "notes"]
 pub struct ChordNote {
     pub chord: String,
+    /// The number of the interval above the root.
     pub degree: Degree,
-    pub interval: Interval,
+    /// The size of the interval above the root, in semitones.
+    pub interval: Semitones,
 }
 
 impl ChordNote {
-    pub fn note(chord: &str, degree: Degree, interval: Interval) -> ChordNote {
+    pub fn note(chord: &str, interval: Interval) -> ChordNote {
         ChordNote {
             chord: chord.to_string(),
-            degree: degree,
-            interval: interval,
+            degree: interval.number,
+            interval: interval.semitones(),
         }
     }
+
+    /// Returns the interval between the root and the note.
+    pub fn as_interval(&self) -> Interval {
+        Interval::from_semitones(self.degree, self.interval)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Queryable, Insertable)]
@@ -29,6 +36,11 @@ impl ChordNote {
 pub struct ChordName {
     pub chord: String,
     pub alternative_name: String,
+    /// True if this is the name that should be used when writing the chord.
+    pub preferred: bool,
+    /// The style of chord symbols the name is preferred in: "pop", "jazz" or
+    /// "long".
+    pub style: String,
 }
 
 impl ChordName {
@@ -36,6 +48,64 @@ impl ChordName {
         ChordName {
             chord: chord.to_string(),
             alternative_name: alternative_name.to_string(),
+            preferred: false,
+            style: ChordSymbols::Pop.name().to_string(),
+        }
+    }
+
+    pub fn preferred_name(chord: &str, alternative_name: &str) -> ChordName {
+        ChordName {
+            preferred: true,
+            ..ChordName::name(chord, alternative_name)
+        }
+    }
+
+    /// The name used when writing the chord in the given style of symbols.
+    pub fn styled_name(chord: &str, alternative_name: &str, style: ChordSymbols) -> ChordName {
+        ChordName {
+            style: style.name().to_string(),
+            ..ChordName::preferred_name(chord, alternative_name)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Queryable, Insertable)]
+#[table_name = "scale_notes"]
+pub struct ScaleNote {
+    pub scale: String,
+    /// The number of the interval above the root.
+    pub degree: Degree,
+    /// The size of the interval above the root, in semitones.
+    pub interval: Semitones,
+}
+
+impl ScaleNote {
+    pub fn note(scale: &str, interval: Interval) -> ScaleNote {
+        ScaleNote {
+            scale: scale.to_string(),
+            degree: interval.number,
+            interval: interval.semitones(),
+        }
+    }
+
+    /// Returns the interval between the root and the note.
+    pub fn as_interval(&self) -> Interval {
+        Interval::from_semitones(self.degree, self.interval)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Queryable, Insertable)]
+#[table_name = "scale_names"]
+pub struct ScaleName {
+    pub scale: String,
+    pub alternative_name: String,
+}
+
+impl ScaleName {
+    pub fn name(scale: &str, alternative_name: &str) -> ScaleName {
+        ScaleName {
+            scale: scale.to_string(),
+            alternative_name: alternative_name.to_string(),
         }
     }
 }
@@ -58,10 +128,9 @@ pub fn get_quality(name: &str, conn: &SqliteConnection) -> Option<Quality> {
         .ok()?;
 
     // If the full name was found, use that. Otherwise, use the given name.
-    let searched_name: String;
-    match primary_name.as_slice() {
-        [found_name] => searched_name = found_name.clone(),
-        [] => searched_name = name.trim().to_owned(),
+    let searched_name = match primary_name.as_slice() {
+        [found_name] => found_name.clone(),
+        [] => name.trim().to_owned(),
         _ => return None,
     };
 
@@ -69,7 +138,7 @@ pub fn get_quality(name: &str, conn: &SqliteConnection) -> Option<Quality> {
         .filter(notes::chord.eq(searched_name))
         .load::<ChordNote>(conn)
         .ok()
-        .map(|ns| ns.into_iter().map(|n| (n.degree, n.interval)).collect());
+        .map(|ns| ns.into_iter().map(|n| n.as_interval()).collect());
 
     // If the query returns no notes, the chord does not exist!
     if quality == Some(vec![]) {
@@ -79,26 +148,111 @@ pub fn get_quality(name: &str, conn: &SqliteConnection) -> Option<Quality> {
     }
 }
 
+/// Returns the full name and quality of every chord in the database, sorted
+/// by name. Each quality is sorted by interval.
+pub fn get_all_chords(conn: &SqliteConnection) -> Option<Vec<(String, Quality)>> {
+    let all_notes = notes::table
+        .order((notes::chord, notes::degree, notes::interval))
+        .load::<ChordNote>(conn)
+        .ok()?;
+
+    let mut chords: Vec<(String, Quality)> = vec![];
+    for note in all_notes {
+        let interval = note.as_interval();
+        match chords.last_mut() {
+            Some((chord, quality)) if *chord == note.chord => quality.push(interval),
+            _ => chords.push((note.chord, vec![interval])),
+        }
+    }
+    Some(chords)
+}
+
+/// Search the chord database for a chord with exactly the notes in
+/// `quality`, and return its full name, such as "minor seventh".
+pub fn get_chord(quality: &[Interval], conn: &SqliteConnection) -> Option<String> {
+    let mut wanted = quality.to_vec();
+    wanted.sort_unstable();
+
+    get_all_chords(conn)?
+        .into_iter()
+        .find(|(_, found)| *found == wanted)
+        .map(|(chord, _)| chord)
+}
+
+/// Search the chord database for a chord with exactly the notes in
+/// `quality`, and return the name it should be written with, such as "m7".
+/// Chords without a preferred abbreviation are written with their full name.
+pub fn get_name(quality: &[Interval], conn: &SqliteConnection) -> Option<String> {
+    get_styled_name(quality, ChordSymbols::Pop, conn)
+}
+
+/// Like `get_name`, but returns the name preferred in the given style of
+/// chord symbols, such as "-7" in jazz. If the style has no name of its own
+/// for the chord, the pop name is used.
+pub fn get_styled_name(
+    quality: &[Interval],
+    style: ChordSymbols,
+    conn: &SqliteConnection,
+) -> Option<String> {
+    let chord = get_chord(quality, conn)?;
+    let preferred = |style: ChordSymbols| {
+        names::table
+            .filter(names::chord.eq(&chord))
+            .filter(names::preferred.eq(true))
+            .filter(names::style.eq(style.name()))
+            .select(names::alternative_name)
+            .first::<String>(conn)
+            .optional()
+    };
+
+    let styled = match preferred(style).ok()? {
+        Some(name) => Some(name),
+        None => preferred(ChordSymbols::Pop).ok()?,
+    };
+    Some(styled.unwrap_or(chord))
+}
+
+/// Search the scale database for a scale with the given name, such as
+/// "dorian" or "harmonic minor". Returns the intervals above the root of every
+/// note apart from the root, in ascending order, or `None` if the scale is
+/// not found.
+pub fn get_scale(name: &str, conn: &SqliteConnection) -> Option<Quality> {
+    let name = name.trim();
+    let primary_name = scale_names::table
+        .filter(scale_names::alternative_name.eq(name))
+        .select(scale_names::scale)
+        .first::<String>(conn)
+        .optional()
+        .ok()?
+        .unwrap_or_else(|| name.to_owned());
+
+    let pattern: Quality = scale_notes::table
+        .filter(scale_notes::scale.eq(primary_name))
+        .order(scale_notes::interval)
+        .load::<ScaleNote>(conn)
+        .ok()?
+        .into_iter()
+        .map(|n| n.as_interval())
+        .collect();
+
+    if pattern.is_empty() {
+        None
+    } else {
+        Some(pattern)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     #[test]
     fn insert() {
-        use degrees::*;
         use intervals::*;
         let conn = initialise_database().unwrap();
 
         let inserted_notes = vec![
-            ChordNote {
-                chord: "maj".to_string(),
-                degree: III,
-                interval: Maj3rd,
-            },
-            ChordNote {
-                chord: "maj".to_string(),
-                degree: V,
-                interval: Per5th,
-            },
+            ChordNote::note("maj", Maj3rd),
+            ChordNote::note("maj", Per5th),
         ];
 
         // We are inserting two notes, make sure that is the case
@@ -113,6 +267,8 @@ mod tests {
         let inserted_name = ChordName {
             chord: "maj".to_string(),
             alternative_name: "major".to_string(),
+            preferred: true,
+            style: "pop".to_string(),
         };
 
         assert_eq!(
@@ -127,15 +283,10 @@ mod tests {
     #[test]
     fn retrieve() {
         use super::*;
-        use degrees::*;
         use intervals::*;
         let conn = initialise_database().unwrap();
 
-        let new_note = ChordNote {
-            chord: "maj".to_string(),
-            degree: III,
-            interval: Maj3rd,
-        };
+        let new_note = ChordNote::note("maj", Maj3rd);
 
         assert_eq!(
             1,
@@ -152,5 +303,41 @@ mod tests {
 
         assert_eq!(retrieved_notes.len(), 1);
         assert_eq!(retrieved_notes[0], new_note);
+        assert_eq!(retrieved_notes[0].as_interval(), Maj3rd);
+    }
+
+    #[test]
+    fn name_lookup() {
+        use crate::chord_library;
+        use intervals::*;
+        let conn = initialise_database().unwrap();
+        chord_library::populate_database(&conn);
+
+        assert_eq!(
+            get_chord(&[Min3rd, Dim5th, Min7th], &conn),
+            Some("half-diminished seventh".to_owned())
+        );
+        assert_eq!(
+            get_name(&[Min7th, Per5th, Min3rd], &conn),
+            Some("m7".to_owned())
+        );
+        assert_eq!(get_name(&[Maj3rd, Per5th], &conn), Some("".to_owned()));
+        assert_eq!(get_name(&[Maj2nd, Aug5th], &conn), None);
+
+        let dim7 = get_quality("dim7", &conn).unwrap();
+        assert_eq!(get_name(&dim7, &conn), Some("dim7".to_owned()));
+
+        let m7 = [Min3rd, Per5th, Min7th];
+        let styled = |quality: &[Interval], style| get_styled_name(quality, style, &conn);
+        assert_eq!(styled(&m7, ChordSymbols::Jazz), Some("-7".to_owned()));
+        assert_eq!(
+            styled(&m7, ChordSymbols::Long),
+            Some("minor seven".to_owned())
+        );
+        // Jazz has no name of its own for the dominant seventh.
+        assert_eq!(
+            styled(&[Maj3rd, Per5th, Min7th], ChordSymbols::Jazz),
+            Some("7".to_owned())
+        );
     }
 }
@@ -0,0 +1,260 @@
+use crate::analysis::{is_key_dominant, is_leading_tone, is_tonic, root_distance};
+use crate::music_theory::*;
+use std::fmt;
+
+/// The role a chord plays in leading the music towards or away from the
+/// tonic.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Function {
+    /// At rest: I, iii and vi in major keys.
+    Tonic,
+    /// Leading to the dominant: ii and IV, and chords borrowed in their
+    /// place such as iv and bII.
+    Predominant,
+    /// Leading to the tonic: V and vii°, and I64 before V.
+    Dominant,
+}
+
+impl Function {
+    /// Returns the abbreviation used when labelling chords, e.g. "PD".
+    pub fn abbreviation(self) -> &'static str {
+        match self {
+            Function::Tonic => "T",
+            Function::Predominant => "PD",
+            Function::Dominant => "D",
+        }
+    }
+}
+
+/// The ways a phrase can end.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum CadenceKind {
+    /// V to I.
+    Authentic,
+    /// IV to I.
+    Plagal,
+    /// Ending on V.
+    Half,
+    /// V to vi, instead of the expected I.
+    Deceptive,
+    /// iv6 to V in a minor key.
+    PhrygianHalf,
+}
+
+impl fmt::Display for CadenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CadenceKind::Authentic => write!(f, "authentic"),
+            CadenceKind::Plagal => write!(f, "plagal"),
+            CadenceKind::Half => write!(f, "half"),
+            CadenceKind::Deceptive => write!(f, "deceptive"),
+            CadenceKind::PhrygianHalf => write!(f, "phrygian half"),
+        }
+    }
+}
+
+/// A cadence made by two neighbouring chords of a progression.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Cadence {
+    pub kind: CadenceKind,
+    /// Index of the chord which ends the cadence. The cadence starts on the
+    /// chord before it.
+    pub end: usize,
+}
+
+/// The harmonic function of every chord of a progression, and the cadences
+/// found in it.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct FunctionalAnalysis {
+    /// The function of each chord, or `None` if it has no clear function in
+    /// the key.
+    pub functions: Vec<Option<Function>>,
+    pub cadences: Vec<Cadence>,
+}
+
+/// Returns the number of semitones from the root of the chord up to its
+/// bass, from 0 to 11.
+fn bass_distance(chord: &Chord) -> Semitones {
+    chord
+        .bass
+        .map(|bass| (pitch_class(bass) - pitch_class(chord.root_letter())).rem_euclid(12))
+        .unwrap_or(0)
+}
+
+fn has_third(chord: &Chord, third: Interval) -> bool {
+    chord.quality.contains(&third)
+}
+
+/// Returns true if the chord is V or vii°, which lead to the tonic.
+fn is_dominant_chord(key: &Key, chord: &Chord) -> bool {
+    is_key_dominant(key, chord)
+        || (root_distance(key, chord) == intervals::Maj7th.semitones()
+            && is_leading_tone(&chord.quality))
+}
+
+/// Returns the function of the chord in the key, if it has a clear one.
+/// `next` is the chord that follows, which is needed to tell a cadential
+/// I64 from a tonic chord.
+pub fn function(key: &Key, chord: &Chord, next: Option<&Chord>) -> Option<Function> {
+    use intervals::*;
+    let minor = key.is_minor();
+    if is_tonic(key, chord)
+        && bass_distance(chord) == intervals::Per5th.semitones()
+        && next.map(|n| is_key_dominant(key, n)).unwrap_or(false)
+    {
+        return Some(Function::Dominant);
+    }
+
+    match root_distance(key, chord) {
+        0 => Some(Function::Tonic),
+        3 if minor && has_third(chord, Maj3rd) => Some(Function::Tonic),
+        4 if !minor && has_third(chord, Min3rd) => Some(Function::Tonic),
+        8 if minor && has_third(chord, Maj3rd) => Some(Function::Tonic),
+        9 if !minor && has_third(chord, Min3rd) => Some(Function::Tonic),
+        1 | 2 | 5 => Some(Function::Predominant),
+        8 if !minor => Some(Function::Predominant),
+        7 | 10 | 11 => Some(Function::Dominant),
+        _ => None,
+    }
+}
+
+/// Returns true if the chord is vi in a major key or VI in a minor key, the
+/// chord a deceptive cadence resolves to.
+fn is_submediant(key: &Key, chord: &Chord) -> bool {
+    use intervals::*;
+    match (key.is_minor(), root_distance(key, chord)) {
+        (false, 9) => has_third(chord, Min3rd),
+        (true, 8) => has_third(chord, Maj3rd),
+        _ => false,
+    }
+}
+
+/// Returns the cadence made by going from `from` to `to`, if there is one.
+/// `after` is the chord which follows `to`, if any: V is only a half cadence
+/// if the music stops there, rather than moving on to the tonic.
+fn cadence(key: &Key, from: &Chord, to: &Chord, after: Option<&Chord>) -> Option<CadenceKind> {
+    use intervals::*;
+    let resolves = |c: &Chord| is_tonic(key, c) || is_submediant(key, c) || is_key_dominant(key, c);
+    let is_iv6 = key.is_minor()
+        && root_distance(key, from) == intervals::Per4th.semitones()
+        && has_third(from, Min3rd)
+        && bass_distance(from) == intervals::Min3rd.semitones();
+
+    if is_dominant_chord(key, from) && is_tonic(key, to) {
+        Some(CadenceKind::Authentic)
+    } else if is_key_dominant(key, from) && is_submediant(key, to) {
+        Some(CadenceKind::Deceptive)
+    } else if root_distance(key, from) == intervals::Per4th.semitones() && is_tonic(key, to) {
+        Some(CadenceKind::Plagal)
+    } else if is_key_dominant(key, to)
+        && !is_key_dominant(key, from)
+        && !after.map(resolves).unwrap_or(false)
+    {
+        if is_iv6 {
+            Some(CadenceKind::PhrygianHalf)
+        } else {
+            Some(CadenceKind::Half)
+        }
+    } else {
+        None
+    }
+}
+
+/// Labels every chord with its harmonic function in the key, and finds the
+/// authentic, plagal, half, deceptive and Phrygian half cadences.
+pub fn label(key: &Key, chords: &[Chord]) -> FunctionalAnalysis {
+    let functions = chords
+        .iter()
+        .enumerate()
+        .map(|(i, chord)| function(key, chord, chords.get(i + 1)))
+        .collect();
+    let cadences = (1..chords.len())
+        .filter_map(|end| {
+            cadence(key, &chords[end - 1], &chords[end], chords.get(end + 1))
+                .map(|kind| Cadence { kind, end })
+        })
+        .collect();
+    FunctionalAnalysis {
+        functions,
+        cadences,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music_theory::tests::chord;
+    use intervals::*;
+    use Letter::*;
+
+    #[test]
+    fn functions() {
+        use Function::*;
+        let major = vec![Maj3rd, Per5th];
+        let minor = vec![Min3rd, Per5th];
+        let chords = vec![
+            chord(C, major.clone()),
+            chord(A, minor.clone()),
+            chord(D, vec![Min3rd, Per5th, Min7th]),
+            chord(C, major.clone()).with_bass(Some(G)),
+            chord(G, vec![Maj3rd, Per5th, Min7th]),
+            chord(C, major.clone()),
+            chord(Fsh, major.clone()),
+        ];
+        let analysis = label(&Key::major(C), &chords);
+        assert_eq!(
+            analysis.functions,
+            vec![
+                Some(Tonic),
+                Some(Tonic),
+                Some(Predominant),
+                Some(Dominant),
+                Some(Dominant),
+                Some(Tonic),
+                None
+            ]
+        );
+        assert_eq!(Predominant.abbreviation(), "PD");
+    }
+
+    #[test]
+    fn cadences() {
+        use CadenceKind::*;
+        let major = vec![Maj3rd, Per5th];
+        let minor = vec![Min3rd, Per5th];
+        let dom7 = vec![Maj3rd, Per5th, Min7th];
+        let kinds = |key: Key, chords: &[Chord]| -> Vec<(CadenceKind, usize)> {
+            label(&key, chords)
+                .cadences
+                .iter()
+                .map(|c| (c.kind, c.end))
+                .collect()
+        };
+
+        let chords = vec![
+            chord(C, major.clone()),
+            chord(F, major.clone()),
+            chord(G, dom7.clone()),
+            chord(A, minor.clone()),
+            chord(F, major.clone()),
+            chord(G, dom7.clone()),
+            chord(C, major.clone()),
+            chord(F, major.clone()),
+            chord(C, major.clone()),
+            chord(D, minor.clone()),
+            chord(G, major.clone()),
+        ];
+        assert_eq!(
+            kinds(Key::major(C), &chords),
+            vec![(Deceptive, 3), (Authentic, 6), (Plagal, 8), (Half, 10)]
+        );
+
+        let chords = vec![
+            chord(A, minor.clone()),
+            chord(D, minor.clone()).with_bass(Some(F)),
+            chord(E, major.clone()),
+        ];
+        assert_eq!(kinds(Key::minor(A), &chords), vec![(PhrygianHalf, 2)]);
+        assert_eq!(PhrygianHalf.to_string(), "phrygian half");
+    }
+}
@@ -0,0 +1,146 @@
+use crate::database::*;
+use crate::music_theory::*;
+use diesel::sqlite::SqliteConnection;
+
+/// One way of naming a set of notes as a chord.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Identification {
+    /// The chord, with its root in the fourth octave. Its bass is set if
+    /// the lowest note is not the root: either one of the chord's notes (an
+    /// inversion) or a note outside of the chord.
+    pub chord: Chord,
+    /// Full name of the chord's quality in the database, e.g. "minor sixth".
+    pub name: String,
+    /// How plausible this reading is. Higher is better.
+    pub score: i32,
+}
+
+/// Score given to a reading with the root in the bass.
+const ROOT_POSITION: i32 = 100;
+/// Score given to a reading with a chord tone other than the root in the bass.
+const INVERSION: i32 = 80;
+/// Score given to a reading with a note outside of the chord in the bass.
+const SLASH: i32 = 60;
+/// Subtracted from the score for every note of the chord, so that simpler
+/// chords are preferred.
+const NOTE_PENALTY: i32 = 5;
+
+/// Names every chord in the database that could be made up of `notes`. The
+/// first note is taken to be the bass. Every note is tried as the root, and
+/// readings where the bass is left out of the chord are also considered, as
+/// in C/D. The result is sorted from the most to the least plausible reading.
+pub fn identify(notes: &[Letter], conn: &SqliteConnection) -> Vec<Identification> {
+    let bass = match notes.first() {
+        Some(bass) => *bass,
+        None => return vec![],
+    };
+    let chords = get_all_chords(conn).unwrap_or_default();
+
+    // Every distinct note is a possible root. Keep the spelling it was
+    // typed with.
+    let mut roots: Vec<Letter> = vec![];
+    for note in notes {
+        if !roots.contains(note) {
+            roots.push(*note);
+        }
+    }
+
+    let mut readings = vec![];
+    for root in &roots {
+        let all = interval_set(*root, notes.iter());
+        let without_bass = interval_set(*root, notes.iter().filter(|n| **n != bass));
+
+        for (name, quality) in &chords {
+            let mut chord_set: Vec<i32> = quality
+                .iter()
+                .map(|i| i.semitones().rem_euclid(12))
+                .chain(std::iter::once(0))
+                .collect();
+            chord_set.sort_unstable();
+            chord_set.dedup();
+
+            let score = if chord_set == all && *root == bass {
+                ROOT_POSITION
+            } else if chord_set == all {
+                INVERSION
+            } else if chord_set == without_bass && *root != bass {
+                SLASH
+            } else {
+                continue;
+            };
+
+            readings.push(Identification {
+                chord: Chord {
+                    root: LetterOctave(*root, 4),
+                    quality: quality.clone(),
+                    bass: if *root == bass { None } else { Some(bass) },
+                },
+                name: name.clone(),
+                score: score - NOTE_PENALTY * chord_set.len() as i32,
+            });
+        }
+    }
+
+    readings.sort_by(|a, b| b.score.cmp(&a.score).then(a.name.cmp(&b.name)));
+    readings
+}
+
+/// Returns the distinct intervals between `root` and each of the `notes`,
+/// reduced to within an octave and sorted.
+fn interval_set<'a>(root: Letter, notes: impl Iterator<Item = &'a Letter>) -> Vec<Semitones> {
+    let mut set: Vec<Semitones> = notes
+        .map(|n| (pitch_class(*n) - pitch_class(root)).rem_euclid(12))
+        .collect();
+    set.sort_unstable();
+    set.dedup();
+    set
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chord_library;
+    use pitch_calc::Letter::*;
+
+    fn readings(notes: &[Letter]) -> Vec<(Letter, String, Option<Letter>)> {
+        let conn = initialise_database().unwrap();
+        chord_library::populate_database(&conn);
+        identify(notes, &conn)
+            .into_iter()
+            .map(|i| (i.chord.root_letter(), i.name, i.chord.bass))
+            .collect()
+    }
+
+    #[test]
+    fn identify_chords() {
+        let found = readings(&[E, G, Bb, D]);
+        assert_eq!(found[0], (E, "half-diminished seventh".to_owned(), None));
+        assert_eq!(found[1], (G, "minor sixth".to_owned(), Some(E)));
+
+        let found = readings(&[C, E, G]);
+        assert_eq!(found, vec![(C, "major".to_owned(), None)]);
+
+        let found = readings(&[E, G, C]);
+        assert_eq!(found, vec![(C, "major".to_owned(), Some(E))]);
+
+        let found = readings(&[C, E, G, A]);
+        assert_eq!(found[0], (C, "major sixth".to_owned(), None));
+        assert_eq!(found[1], (A, "minor seventh".to_owned(), Some(C)));
+    }
+
+    #[test]
+    fn identify_slash_chords() {
+        let found = readings(&[D, C, E, G]);
+        assert!(found.contains(&(C, "major".to_owned(), Some(D))));
+
+        // Diminished seventh chords are symmetrical, so every note is a root.
+        // Leaving the bass out gives a diminished triad over B.
+        let found = readings(&[B, D, F, Ab]);
+        assert_eq!(found.len(), 5);
+        assert_eq!(found[0], (B, "diminished seventh".to_owned(), None));
+        assert_eq!(found[4], (D, "diminished".to_owned(), Some(B)));
+
+        assert_eq!(readings(&[]), vec![]);
+        assert_eq!(readings(&[C, Db, D]), vec![]);
+    }
+}
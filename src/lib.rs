@@ -5,6 +5,7 @@ extern crate diesel_migrations;
 extern crate dasp;
 extern crate enum_primitive_derive;
 extern crate find_folder; // For easily finding the assets folder.
+extern crate hound; // To read and write WAV files.
 extern crate nom;
 extern crate num_traits;
 extern crate pitch_calc; // To work with musical notes.
@@ -12,9 +13,26 @@ extern crate portaudio; // For audio I/O
 extern crate rustyline; // To convert portaudio sample buffers to frames.
                         //extern crate sampler;
 
+pub mod analysis;
+pub mod audio;
 pub mod chord_library;
+// Diesel's derives and `table!` macro generate impl blocks inside of functions.
+#[allow(non_local_definitions)]
 pub mod database;
+pub mod function;
+pub mod identify;
 pub mod music_theory;
+pub mod negative_harmony;
+pub mod neo_riemannian;
 pub mod parser;
+pub mod pitch_class_set;
+pub mod progression;
+pub mod sampler;
+pub mod scale_library;
+#[allow(non_local_definitions)]
 pub mod schema;
 pub mod sequencer;
+pub mod substitution;
+pub mod symbol;
+pub mod voice_leading;
+pub mod voicing;
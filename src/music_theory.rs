@@ -1,9 +1,8 @@
-pub use pitch_calc::{Letter, LetterOctave, Step};
+pub use pitch_calc::{Letter, LetterOctave, Octave, Step};
 use std::fmt;
 
 use pitch_calc::letter_octave_from_step;
 
-// TODO: implement Display for Degree
 pub type Degree = i32;
 #[allow(dead_code)]
 /// Helper module exporting constants for roman numeral chord and scale degrees.
@@ -16,87 +15,374 @@ pub mod degrees {
     pub const V: Degree = 5;
     pub const VI: Degree = 6;
     pub const VII: Degree = 7;
+    pub const IX: Degree = 9;
+    pub const XI: Degree = 11;
+    pub const XIII: Degree = 13;
 }
 
-// TODO: implement Display for interval
-pub type Interval = i32;
-#[allow(dead_code, non_upper_case_globals)]
-/// Helper module containing intervals, and their size in semitones.
-pub mod intervals {
-    use super::*;
-    pub const Root: Interval = 0;
-    pub const Min2nd: Interval = 1;
-    pub const Maj2nd: Interval = 2;
-    pub const Min3rd: Interval = 3;
-    pub const Maj3rd: Interval = 4;
-    pub const Per4th: Interval = 5;
+/// Returns the upper-case roman numeral of a scale degree within the octave,
+/// such as "IV" for `degrees::IV`. Compound degrees are reduced first, so
+/// the ninth is written as "II".
+pub fn degree_to_string(degree: Degree) -> String {
+    let numeral = match (degree - 1).rem_euclid(7) {
+        0 => "I",
+        1 => "II",
+        2 => "III",
+        3 => "IV",
+        4 => "V",
+        5 => "VI",
+        _ => "VII",
+    };
+    numeral.to_string()
+}
 
-    pub const Dim5th: Interval = 6;
-    pub const Per5th: Interval = 7;
-    pub const Aug5th: Interval = 8;
+/// A number of semitones, e.g. the distance between two pitches regardless
+/// of how they are spelled.
+pub type Semitones = i32;
 
-    pub const Min6th: Interval = 8;
-    pub const Maj6th: Interval = 9;
-    pub const Dim7th: Interval = 9;
-    pub const Min7th: Interval = 10;
-    pub const Maj7th: Interval = 11;
+/// The quality of an interval, which together with its number tells how
+/// many semitones it spans.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub enum IntervalQuality {
+    /// Unisons, fourths, fifths and octaves, and their compounds.
+    Perfect,
+    Major,
+    Minor,
+    /// Augmented by the given number of semitones: 1 for augmented, 2 for
+    /// doubly augmented.
+    Augmented(i32),
+    /// Diminished by the given number of semitones.
+    Diminished(i32),
+}
 
-    pub const Octave: Interval = 12;
+/// An interval between two spelled notes, such as a minor third or an
+/// augmented fifth. Unlike a number of semitones, this tells apart
+/// intervals which sound the same: an augmented fifth spans as many
+/// semitones as a minor sixth, but is written on a different note.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub struct Interval {
+    /// The generic number of the interval: 1 for a unison, 3 for a third,
+    /// 9 for a ninth.
+    pub number: Degree,
+    pub quality: IntervalQuality,
+}
+
+/// Returns true if the intervals with this number are perfect rather than
+/// major or minor.
+fn is_perfect_number(number: Degree) -> bool {
+    matches!((number - 1).rem_euclid(7), 0 | 3 | 4)
+}
+
+impl Interval {
+    pub const fn new(number: Degree, quality: IntervalQuality) -> Interval {
+        Interval { number, quality }
+    }
+
+    /// Returns the perfect or major interval with this number, i.e. the
+    /// interval between the root of a major scale and its note on `number`.
+    pub fn natural(number: Degree) -> Interval {
+        if is_perfect_number(number) {
+            Interval::new(number, IntervalQuality::Perfect)
+        } else {
+            Interval::new(number, IntervalQuality::Major)
+        }
+    }
+
+    /// Returns the interval with this number which spans `semitones`.
+    pub fn from_semitones(number: Degree, semitones: Semitones) -> Interval {
+        use IntervalQuality::*;
+        let difference = semitones - Interval::natural(number).semitones();
+        let quality = match (is_perfect_number(number), difference) {
+            (_, d) if d > 0 => Augmented(d),
+            (true, 0) => Perfect,
+            (true, d) => Diminished(-d),
+            (false, 0) => Major,
+            (false, -1) => Minor,
+            (false, d) => Diminished(-d - 1),
+        };
+        Interval::new(number, quality)
+    }
+
+    /// Returns the interval with the same number, widened by `accidental`
+    /// semitones, or narrowed if it is negative: a major third flattened is
+    /// a minor third.
+    pub fn altered(self, accidental: Accidental) -> Interval {
+        Interval::from_semitones(self.number, self.semitones() + accidental)
+    }
+
+    /// Number of semitones spanned by the interval.
+    pub fn semitones(self) -> Semitones {
+        use IntervalQuality::*;
+        let octaves = (self.number - 1).div_euclid(7);
+        let natural = Natural::from_index(self.number - 1).semitones() + 12 * octaves;
+        let perfect = is_perfect_number(self.number);
+        natural
+            + match self.quality {
+                Perfect | Major => 0,
+                Minor => -1,
+                Augmented(n) => n,
+                Diminished(n) if perfect => -n,
+                Diminished(n) => -n - 1,
+            }
+    }
+
+    /// Returns true if the interval is wider than an octave, such as a
+    /// ninth.
+    pub fn is_compound(self) -> bool {
+        self.number > 8
+    }
+
+    /// Returns the interval reduced to within an octave: a major ninth
+    /// becomes a major second. Octaves are left as they are.
+    pub fn simple(self) -> Interval {
+        let octaves = ((self.number - 2) / 7).max(0);
+        Interval::from_semitones(self.number - 7 * octaves, self.semitones() - 12 * octaves)
+    }
+
+    /// Returns the interval which adds up to an octave with this one once it
+    /// is made simple: a major third inverts to a minor sixth, and an
+    /// augmented fourth to a diminished fifth.
+    pub fn inverted(self) -> Interval {
+        let simple = self.simple();
+        Interval::from_semitones(9 - simple.number, 12 - simple.semitones())
+    }
+}
+
+impl std::ops::Add for Interval {
+    type Output = Interval;
+
+    /// Stacks one interval on top of the other: a major third on top of a
+    /// minor third is a perfect fifth.
+    fn add(self, other: Interval) -> Interval {
+        Interval::from_semitones(
+            self.number + other.number - 1,
+            self.semitones() + other.semitones(),
+        )
+    }
+}
+
+impl std::ops::Sub for Interval {
+    type Output = Interval;
+
+    /// Returns the interval left over between the two: a perfect fifth minus
+    /// a major third is a minor third.
+    fn sub(self, other: Interval) -> Interval {
+        Interval::from_semitones(
+            self.number - other.number + 1,
+            self.semitones() - other.semitones(),
+        )
+    }
+}
+
+impl std::ops::Add<Interval> for Letter {
+    type Output = Letter;
+
+    /// Returns the note `interval` above, spelled according to the
+    /// interval's number: Db plus a major third is F, not E#.
+    fn add(self, interval: Interval) -> Letter {
+        SpelledNote::from_letter(self).above(interval).letter()
+    }
+}
+
+impl std::ops::Sub<Interval> for Letter {
+    type Output = Letter;
+
+    /// Returns the note `interval` below, spelled according to the
+    /// interval's number.
+    fn sub(self, interval: Interval) -> Letter {
+        SpelledNote::from_letter(self)
+            .above(interval.inverted())
+            .letter()
+    }
+}
+
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Interval) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Interval {
+    /// Orders intervals by number, then by size.
+    fn cmp(&self, other: &Interval) -> std::cmp::Ordering {
+        (self.number, self.semitones()).cmp(&(other.number, other.semitones()))
+    }
+}
+
+impl fmt::Display for Interval {
+    /// Writes the interval in short form, e.g. m3, P5, A4 or M9.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use IntervalQuality::*;
+        let quality = match self.quality {
+            Perfect => "P".to_owned(),
+            Major => "M".to_owned(),
+            Minor => "m".to_owned(),
+            Augmented(n) => "A".repeat(n.max(0) as usize),
+            Diminished(n) => "d".repeat(n.max(0) as usize),
+        };
+        write!(f, "{}{}", quality, self.number)
+    }
+}
+
+impl std::str::FromStr for Interval {
+    type Err = String;
+
+    /// Reads an interval written in short form, e.g. m3, P5, A4, dd7 or M9.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use IntervalQuality::*;
+        let error = || format!("Invalid interval {}", s);
+        let split = s.find(|c: char| c.is_ascii_digit()).ok_or_else(error)?;
+        let (quality, number) = s.split_at(split);
+        let number: Degree = number.parse().map_err(|_| error())?;
+        let count = quality.len() as i32;
+        let perfect = is_perfect_number(number);
+        let quality = match quality {
+            "P" if perfect => Perfect,
+            "M" if !perfect => Major,
+            "m" if !perfect => Minor,
+            q if count > 0 && q.chars().all(|c| c == 'A') => Augmented(count),
+            q if count > 0 && q.chars().all(|c| c == 'd') => Diminished(count),
+            _ => return Err(error()),
+        };
+        if number < 1 {
+            return Err(error());
+        }
+        Ok(Interval::new(number, quality))
+    }
 }
 
 #[allow(dead_code, non_upper_case_globals)]
-// Contains constants of the type (Degree, Interval). Useful mostly for building Chords.
-pub mod degree_intervals {
+/// Helper module containing the intervals used to build chords and scales.
+pub mod intervals {
+    use super::IntervalQuality::*;
     use super::*;
-    use degrees::*;
-    pub const Root: (Degree, Interval) = (I, intervals::Root);
+    pub const Root: Interval = Interval::new(1, Perfect);
+
+    pub const Min2nd: Interval = Interval::new(2, Minor);
+    pub const Maj2nd: Interval = Interval::new(2, Major);
+    pub const Aug2nd: Interval = Interval::new(2, Augmented(1));
+
+    pub const Min3rd: Interval = Interval::new(3, Minor);
+    pub const Maj3rd: Interval = Interval::new(3, Major);
 
-    pub const Min2nd: (Degree, Interval) = (II, intervals::Min2nd);
-    pub const Maj2nd: (Degree, Interval) = (II, intervals::Maj2nd);
+    pub const Dim4th: Interval = Interval::new(4, Diminished(1));
+    pub const Per4th: Interval = Interval::new(4, Perfect);
+    pub const Aug4th: Interval = Interval::new(4, Augmented(1));
 
-    pub const Min3rd: (Degree, Interval) = (III, intervals::Min3rd);
-    pub const Maj3rd: (Degree, Interval) = (III, intervals::Maj3rd);
+    pub const Dim5th: Interval = Interval::new(5, Diminished(1));
+    pub const Per5th: Interval = Interval::new(5, Perfect);
+    pub const Aug5th: Interval = Interval::new(5, Augmented(1));
 
-    pub const Per4th: (Degree, Interval) = (IV, intervals::Per4th);
+    pub const Min6th: Interval = Interval::new(6, Minor);
+    pub const Maj6th: Interval = Interval::new(6, Major);
 
-    pub const Dim5th: (Degree, Interval) = (V, intervals::Dim5th);
-    pub const Per5th: (Degree, Interval) = (V, intervals::Per5th);
-    pub const Aug5th: (Degree, Interval) = (V, intervals::Aug5th);
+    pub const Dim7th: Interval = Interval::new(7, Diminished(1));
+    pub const Min7th: Interval = Interval::new(7, Minor);
+    pub const Maj7th: Interval = Interval::new(7, Major);
 
-    pub const Min6th: (Degree, Interval) = (VI, intervals::Min6th);
-    pub const Maj6th: (Degree, Interval) = (VI, intervals::Maj6th);
+    pub const Octave: Interval = Interval::new(8, Perfect);
 
-    pub const Dim7th: (Degree, Interval) = (VII, intervals::Dim7th);
-    pub const Min7th: (Degree, Interval) = (VII, intervals::Min7th);
-    pub const Maj7th: (Degree, Interval) = (VII, intervals::Maj7th);
+    pub const Min9th: Interval = Interval::new(9, Minor);
+    pub const Maj9th: Interval = Interval::new(9, Major);
+    pub const Aug9th: Interval = Interval::new(9, Augmented(1));
 
-    pub const Octave: (Degree, Interval) = (I, intervals::Octave);
+    pub const Per11th: Interval = Interval::new(11, Perfect);
+    pub const Aug11th: Interval = Interval::new(11, Augmented(1));
+
+    pub const Min13th: Interval = Interval::new(13, Minor);
+    pub const Maj13th: Interval = Interval::new(13, Major);
 }
 
-pub type Quality = Vec<(Degree, Interval)>;
+/// The intervals above the root of a chord or scale, one for each note other
+/// than the root.
+pub type Quality = Vec<Interval>;
+
+/// Returns the interval of the unaltered note of a degree: perfect for
+/// unisons, fourths and fifths and their compounds, major otherwise.
+pub fn natural_interval(degree: Degree) -> Option<Interval> {
+    match degree {
+        1..=7 | 9 | 11 | 13 => Some(Interval::natural(degree)),
+        _ => None,
+    }
+}
 
 /// Chords are composed of the root tone, followed by a list of notes
-/// and their scale degrees.
-#[derive(PartialEq, Eq, Debug)]
+/// and their scale degrees. Unless `bass` is set, the chord is voiced with
+/// the root as its lowest note.
+#[derive(PartialEq, Eq, Clone, Debug)]
 pub struct Chord {
     pub root: LetterOctave,
     pub quality: Quality,
+    /// The lowest note of the chord. If it is one of the chord's notes, the
+    /// chord is inverted. Otherwise, it is played below the root, as in C/D.
+    pub bass: Option<Letter>,
 }
 
-/// Transpose the note by the number of semitones in `interval`.
+/// Transpose the note up by `interval`, spelling the result accordingly:
+/// C up an augmented fifth is G#, while C up a minor sixth is Ab.
 pub fn transpose(note: LetterOctave, interval: Interval) -> LetterOctave {
-    let interval_s = interval as pitch_calc::calc::Step;
-    let (letter, octave) = letter_octave_from_step(note.step() + interval_s);
+    let spelled = SpelledNote::from_letter(note.letter()).above(interval);
+    let octave = transpose_semitones(note, interval.semitones()).octave();
+    LetterOctave(spelled.letter(), octave)
+}
+
+/// Transpose the note by the number of semitones in `semitones`.
+pub fn transpose_semitones(note: LetterOctave, semitones: Semitones) -> LetterOctave {
+    let semitones = semitones as pitch_calc::calc::Step;
+    let (letter, octave) = letter_octave_from_step(note.step() + semitones);
     LetterOctave(letter, octave)
 }
 
+/// Spells the root and each of the notes in `pattern` according to its
+/// degree, along with the octave each note is written in.
+fn spell(root: LetterOctave, pattern: &[Interval]) -> Vec<(SpelledNote, Octave)> {
+    let root_note = SpelledNote::from_letter(root.letter());
+    // Semitones above C0 of the root. Used to work out which octave each
+    // note is written in, as e.g. Cb5 sounds the same as B4.
+    let root_height = root.octave() * 12 + root_note.semitones();
+
+    let mut notes = vec![(root_note, root.octave())];
+    for interval in pattern {
+        let note = root_note.above(*interval);
+        let octave = (root_height + interval.semitones() - note.semitones()).div_euclid(12);
+        notes.push((note, octave));
+    }
+    notes
+}
+
 #[allow(dead_code)]
 impl Chord {
-    /// Returns a new chord, transposed by `interval`.
+    /// Returns the chord built on `root` with the given `quality`, in root
+    /// position.
+    pub fn new(root: LetterOctave, quality: Quality) -> Chord {
+        Chord {
+            root,
+            quality,
+            bass: None,
+        }
+    }
+
+    /// Returns a new chord, transposed up by `interval`. The root and bass
+    /// are spelled according to the interval, so Eb up a minor third is Gb
+    /// rather than F#.
     pub fn transposed(&self, interval: Interval) -> Chord {
         Chord {
             root: transpose(self.root, interval),
             quality: self.quality.clone(),
+            bass: self
+                .bass
+                .map(|b| SpelledNote::from_letter(b).above(interval).letter()),
+        }
+    }
+
+    /// Returns a new chord, transposed by a number of `semitones`, which may
+    /// be negative. As there is no interval to go by, the new root is always
+    /// spelled with sharps.
+    pub fn transposed_semitones(&self, semitones: Semitones) -> Chord {
+        Chord {
+            root: transpose_semitones(self.root, semitones),
+            quality: self.quality.clone(),
+            bass: self.bass.map(|b| b + semitones),
         }
     }
 
@@ -106,11 +392,13 @@ impl Chord {
     }
 
     /// Returns a new chord with the same quality but with a different root,
-    /// determined by `new_root`.
+    /// determined by `new_root`. The bass note moves along with the root.
     pub fn with_root(&self, new_root: LetterOctave) -> Chord {
+        let distance = (new_root.step() - self.root.step()) as Semitones;
         Chord {
             root: new_root,
             quality: self.quality.clone(),
+            bass: self.bass.map(|b| b + distance),
         }
     }
 
@@ -123,10 +411,7 @@ impl Chord {
     /// determined by `new_root`. The new chord will be voiced in the same
     /// octave as the old one.
     pub fn with_root_letter(&self, new_root: Letter) -> Chord {
-        Chord {
-            root: LetterOctave(new_root, self.root.octave()),
-            quality: self.quality.clone(),
-        }
+        self.with_root(LetterOctave(new_root, self.root.octave()))
     }
 
     /// Returns a copy of the chord's Quality.
@@ -138,110 +423,645 @@ impl Chord {
     pub fn with_quality(&self, quality: Quality) -> Chord {
         Chord {
             root: self.root,
-            quality: quality,
+            quality,
+            bass: self.bass,
         }
     }
 
-    /// Returns all of the notes that make up the chord.
-    pub fn notes(&self) -> Vec<LetterOctave> {
-        self.quality
-            .clone()
-            .into_iter()
-            .map(|(_, i)| transpose(self.root, i))
+    /// Returns a new chord with a different bass note, or in root position
+    /// if `bass` is `None`.
+    pub fn with_bass(&self, bass: Option<Letter>) -> Chord {
+        Chord {
+            root: self.root,
+            quality: self.quality.clone(),
+            bass,
+        }
+    }
+
+    /// Returns the chord in its `inversion`: 0 is root position, 1 puts the
+    /// second note of the chord in the bass, 2 the third note and so on.
+    /// Returns `None` if the chord does not have that many notes.
+    pub fn inverted(&self, inversion: usize) -> Option<Chord> {
+        let notes = spell(self.root, &self.quality);
+        let bass = match inversion {
+            0 => None,
+            i => Some(notes.get(i)?.0.letter()),
+        };
+        Some(self.with_bass(bass))
+    }
+
+    /// Returns every note of the chord from the lowest upwards, both as a
+    /// pitch and as a spelled note along with the octave it is written in.
+    fn voiced(&self) -> Vec<(LetterOctave, (SpelledNote, Octave))> {
+        let mut notes: Vec<(LetterOctave, (SpelledNote, Octave))> = self
+            .quality
+            .iter()
+            .map(|i| transpose(self.root, *i))
             .fold(vec![self.root()], |mut ns, n| {
                 ns.push(n);
                 ns
             })
+            .into_iter()
+            .zip(spell(self.root, &self.quality))
+            .collect();
+
+        let bass = match self.bass {
+            Some(bass) if bass != self.root.letter() => bass,
+            _ => return notes,
+        };
+
+        match notes.iter().position(|(n, _)| n.letter() == bass) {
+            // Inversion: move every note below the bass up by octaves.
+            Some(i) => {
+                let lowest = notes[i].0.step();
+                for (note, (_, octave)) in notes.iter_mut() {
+                    while note.step() < lowest {
+                        *note = transpose(*note, intervals::Octave);
+                        *octave += 1;
+                    }
+                }
+                notes.sort_by(|a, b| a.0.step().partial_cmp(&b.0.step()).unwrap());
+            }
+            // The bass is not part of the chord: play it below the root.
+            None => {
+                let mut bass_note = LetterOctave(bass, self.root.octave());
+                if bass_note.step() >= self.root.step() {
+                    bass_note = LetterOctave(bass, self.root.octave() - 1);
+                }
+                let spelled = (SpelledNote::from_letter(bass), bass_note.octave());
+                notes.insert(0, (bass_note, spelled));
+            }
+        }
+        notes
+    }
+
+    /// Returns the spelled notes of the chord, along with the octave each of
+    /// them is written in. Every note is spelled from the root according to
+    /// its scale degree, so the fifth of F#dim is C rather than B#.
+    pub fn spelled_notes(&self) -> Vec<(SpelledNote, Octave)> {
+        self.voiced().into_iter().map(|(_, s)| s).collect()
+    }
+
+    /// Returns an object that writes the chord's notes in the given `style`
+    /// when formatted with `{}`.
+    pub fn display<'a>(&'a self, style: &'a NotationStyle) -> NotesDisplay<'a> {
+        NotesDisplay {
+            spelled: self.spelled_notes(),
+            notes: self.notes(),
+            style,
+        }
+    }
+
+    /// Returns all of the notes that make up the chord, from the lowest
+    /// upwards.
+    pub fn notes(&self) -> Vec<LetterOctave> {
+        self.voiced().into_iter().map(|(n, _)| n).collect()
+    }
+}
+
+/// Scales are composed of the root, followed by the rest of the notes in
+/// ascending order, along with their scale degrees.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Scale {
+    pub root: LetterOctave,
+    pub pattern: Quality,
+}
+
+impl Scale {
+    /// Returns a new scale, transposed up by `interval`.
+    pub fn transposed(&self, interval: Interval) -> Scale {
+        Scale {
+            root: transpose(self.root, interval),
+            pattern: self.pattern.clone(),
+        }
+    }
+
+    /// Returns the root note of the scale.
+    pub fn root(&self) -> LetterOctave {
+        self.root
+    }
+
+    /// Returns a new scale with the same pattern but with a different root,
+    /// determined by `new_root`.
+    pub fn with_root(&self, new_root: LetterOctave) -> Scale {
+        Scale {
+            root: new_root,
+            pattern: self.pattern.clone(),
+        }
+    }
+
+    /// Returns true if `note` is part of the scale, in any octave.
+    pub fn contains(&self, note: Letter) -> bool {
+        self.notes().into_iter().any(|n| n.letter() == note)
+    }
+
+    /// Returns the spelled notes of the scale, along with the octave each of
+    /// them is written in.
+    pub fn spelled_notes(&self) -> Vec<(SpelledNote, Octave)> {
+        spell(self.root, &self.pattern)
+    }
+
+    /// Returns an object that writes the scale's notes in the given `style`
+    /// when formatted with `{}`.
+    pub fn display<'a>(&'a self, style: &'a NotationStyle) -> NotesDisplay<'a> {
+        NotesDisplay {
+            spelled: self.spelled_notes(),
+            notes: self.notes(),
+            style,
+        }
+    }
+
+    /// Returns all of the notes of the scale, from the root upwards.
+    pub fn notes(&self) -> Vec<LetterOctave> {
+        let mut notes = vec![self.root];
+        notes.extend(self.pattern.iter().map(|i| transpose(self.root, *i)));
+        notes
+    }
+}
+
+/// How many thirds to stack on top of each other when building chords out
+/// of a scale.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum ChordSize {
+    Triad,
+    Seventh,
+    Ninth,
+}
+
+impl ChordSize {
+    /// Number of notes in a chord of this size, including the root.
+    pub fn notes(self) -> usize {
+        match self {
+            ChordSize::Triad => 3,
+            ChordSize::Seventh => 4,
+            ChordSize::Ninth => 5,
+        }
+    }
+}
+
+impl Scale {
+    /// Builds a chord on every note of the scale by stacking every other
+    /// note of the scale on top of it. The chord tones are spelled according
+    /// to the scale, so the chords of Eb major are built on Ab rather than G#.
+    /// Returns `None` unless the scale has seven notes, as chords are only
+    /// built in thirds that way.
+    pub fn harmonize(&self, size: ChordSize) -> Option<Vec<Chord>> {
+        let spelled = self.spelled_notes();
+        let mut steps = vec![intervals::Root];
+        steps.extend(self.pattern.iter().cloned());
+        let length = steps.len();
+        if length != 7 {
+            return None;
+        }
+
+        let chords = (0..length)
+            .map(|i| {
+                let (root_note, root_octave) = spelled[i];
+                let quality = (1..size.notes())
+                    .map(|k| {
+                        let position = i + 2 * k;
+                        let octaves = (position / length) as i32;
+                        let interval = steps[position % length];
+                        let raised = Interval::from_semitones(
+                            interval.number + 7 * octaves,
+                            interval.semitones() + 12 * octaves,
+                        );
+                        raised - steps[i]
+                    })
+                    .collect();
+                Chord::new(root_note.letter_octave(root_octave), quality)
+            })
+            .collect();
+        Some(chords)
+    }
+}
+
+/// A key is a tonic along with the scale that the music is built from,
+/// which always has seven degrees.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Key {
+    scale: Scale,
+}
+
+impl Key {
+    /// The major key with `tonic` as its first degree.
+    pub fn major(tonic: Letter) -> Key {
+        use intervals::*;
+        Key {
+            scale: Scale {
+                root: LetterOctave(tonic, 4),
+                pattern: vec![Maj2nd, Maj3rd, Per4th, Per5th, Maj6th, Maj7th],
+            },
+        }
     }
+
+    /// The minor key with `tonic` as its first degree, using the notes of
+    /// the natural minor scale.
+    pub fn minor(tonic: Letter) -> Key {
+        use intervals::*;
+        Key {
+            scale: Scale {
+                root: LetterOctave(tonic, 4),
+                pattern: vec![Maj2nd, Min3rd, Per4th, Per5th, Min6th, Min7th],
+            },
+        }
+    }
+
+    /// Returns the scale of the key.
+    pub fn scale(&self) -> &Scale {
+        &self.scale
+    }
+
+    /// Returns the first degree of the key.
+    pub fn tonic(&self) -> Letter {
+        self.scale.root.letter()
+    }
+
+    /// Returns true if the third degree of the key is a minor third above
+    /// the tonic.
+    pub fn is_minor(&self) -> bool {
+        self.scale.pattern.contains(&intervals::Min3rd)
+    }
+
+    /// Returns the chords built on every degree of the key.
+    pub fn diatonic_chords(&self, size: ChordSize) -> Vec<Chord> {
+        self.scale.harmonize(size).expect("a key has seven degrees")
+    }
+
+    /// Returns the note on `degree` of the key, from 1 for the tonic up to
+    /// 7, raised or lowered by `accidental`: the flat sixth degree of C is Ab.
+    pub fn degree(&self, degree: Degree, accidental: Accidental) -> SpelledNote {
+        let (note, _) = self.scale.spelled_notes()[(degree - 1).rem_euclid(7) as usize];
+        SpelledNote::new(note.natural, note.accidental + accidental)
+    }
+}
+
+/// The seven natural note names, i.e. the white keys of the piano.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Natural {
+    C,
+    D,
+    E,
+    F,
+    G,
+    A,
+    B,
 }
 
-static mut USE_FLATS: bool = false;
+impl Natural {
+    /// Position of the note within the C major scale, starting from 0.
+    pub fn index(self) -> i32 {
+        use Natural::*;
+        match self {
+            C => 0,
+            D => 1,
+            E => 2,
+            F => 3,
+            G => 4,
+            A => 5,
+            B => 6,
+        }
+    }
 
-pub fn set_use_flats(flats: bool) {
-    unsafe {
-        USE_FLATS = flats;
+    /// Returns the natural note found at `index` steps above C, wrapping
+    /// around every octave.
+    pub fn from_index(index: i32) -> Natural {
+        use Natural::*;
+        match index.rem_euclid(7) {
+            0 => C,
+            1 => D,
+            2 => E,
+            3 => F,
+            4 => G,
+            5 => A,
+            _ => B,
+        }
+    }
+
+    /// Number of semitones between C and the natural note.
+    pub fn semitones(self) -> i32 {
+        use Natural::*;
+        match self {
+            C => 0,
+            D => 2,
+            E => 4,
+            F => 5,
+            G => 7,
+            A => 9,
+            B => 11,
+        }
     }
 }
 
-pub fn get_use_flats() -> bool {
-    unsafe { USE_FLATS }
+/// Number of semitones by which a natural note is altered. Positive values
+/// are sharps, negative values are flats.
+pub type Accidental = i32;
+
+/// A note spelled as a natural note plus an accidental. Unlike `Letter`,
+/// this can tell apart enharmonic notes such as F# and Gb, and can represent
+/// double sharps & flats.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct SpelledNote {
+    pub natural: Natural,
+    pub accidental: Accidental,
 }
 
-pub fn letter_to_string(letter: Letter) -> String {
+/// Returns the number of semitones between C and `letter`, from 0 to 11.
+pub fn pitch_class(letter: Letter) -> i32 {
     use pitch_calc::Letter::*;
-    let flats = get_use_flats();
-    let s = match letter {
-        C => "C",
-        D => "D",
-        E => "E",
-        F => "F",
-        G => "G",
-        A => "A",
-        B => "B",
-
-        Db | Csh => {
-            if flats {
-                "Db"
-            } else {
-                "C#"
-            }
+    match letter {
+        C => 0,
+        Csh | Db => 1,
+        D => 2,
+        Dsh | Eb => 3,
+        E => 4,
+        F => 5,
+        Fsh | Gb => 6,
+        G => 7,
+        Gsh | Ab => 8,
+        A => 9,
+        Ash | Bb => 10,
+        B => 11,
+    }
+}
+
+impl SpelledNote {
+    pub fn new(natural: Natural, accidental: Accidental) -> SpelledNote {
+        SpelledNote {
+            natural,
+            accidental,
         }
+    }
 
-        Eb | Dsh => {
-            if flats {
-                "Eb"
-            } else {
-                "D#"
-            }
+    /// Spells `letter` the way it is written: `Csh` becomes C#, `Db` becomes Db.
+    pub fn from_letter(letter: Letter) -> SpelledNote {
+        use pitch_calc::Letter::*;
+        match letter {
+            C => SpelledNote::new(Natural::C, 0),
+            Csh => SpelledNote::new(Natural::C, 1),
+            Db => SpelledNote::new(Natural::D, -1),
+            D => SpelledNote::new(Natural::D, 0),
+            Dsh => SpelledNote::new(Natural::D, 1),
+            Eb => SpelledNote::new(Natural::E, -1),
+            E => SpelledNote::new(Natural::E, 0),
+            F => SpelledNote::new(Natural::F, 0),
+            Fsh => SpelledNote::new(Natural::F, 1),
+            Gb => SpelledNote::new(Natural::G, -1),
+            G => SpelledNote::new(Natural::G, 0),
+            Gsh => SpelledNote::new(Natural::G, 1),
+            Ab => SpelledNote::new(Natural::A, -1),
+            A => SpelledNote::new(Natural::A, 0),
+            Ash => SpelledNote::new(Natural::A, 1),
+            Bb => SpelledNote::new(Natural::B, -1),
+            B => SpelledNote::new(Natural::B, 0),
         }
+    }
 
-        Gb | Fsh => {
-            if flats {
-                "Gb"
-            } else {
-                "F#"
-            }
+    /// Number of semitones between C and the note. May fall outside of the
+    /// 0 to 11 range for notes such as Cb or B#.
+    pub fn semitones(self) -> i32 {
+        self.natural.semitones() + self.accidental
+    }
+
+    /// Returns the `Letter` that sounds the same as this note. The letter
+    /// keeps the note's spelling where it can: Db becomes `Db`, not `Csh`.
+    pub fn letter(self) -> Letter {
+        use pitch_calc::Letter::*;
+        match (self.natural, self.accidental) {
+            (Natural::C, 1) => Csh,
+            (Natural::D, -1) => Db,
+            (Natural::D, 1) => Dsh,
+            (Natural::E, -1) => Eb,
+            (Natural::F, 1) => Fsh,
+            (Natural::G, -1) => Gb,
+            (Natural::G, 1) => Gsh,
+            (Natural::A, -1) => Ab,
+            (Natural::A, 1) => Ash,
+            (Natural::B, -1) => Bb,
+            _ => C + self.semitones(),
         }
+    }
 
-        Ab | Gsh => {
-            if flats {
-                "Ab"
-            } else {
-                "G#"
-            }
+    /// Returns the pitch of the note when written in `octave`. Notes such as
+    /// Cb5 sound in a different octave to the one they are written in.
+    pub fn letter_octave(self, octave: Octave) -> LetterOctave {
+        LetterOctave(self.letter(), octave + self.semitones().div_euclid(12))
+    }
+
+    /// Returns the note which is `interval` above this one, spelled
+    /// according to the interval's number: a major third above F# is A#, not
+    /// Bb.
+    pub fn above(self, interval: Interval) -> SpelledNote {
+        let natural = Natural::from_index(self.natural.index() + interval.number - 1);
+        let difference =
+            (self.semitones() + interval.semitones() - natural.semitones()).rem_euclid(12);
+        let accidental = if difference > 6 {
+            difference - 12
+        } else {
+            difference
+        };
+        SpelledNote::new(natural, accidental)
+    }
+}
+
+impl fmt::Display for SpelledNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", NotationStyle::default().note_to_string(*self))
+    }
+}
+
+/// Which accidental to use for notes that could be written either way.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Accidentals {
+    Sharps,
+    Flats,
+}
+
+/// Styles of chord symbols, each with its own preferred names for chords.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum ChordSymbols {
+    /// Bbm7b5, Cmaj7
+    Pop,
+    /// Bbø7, CΔ7, D-7
+    Jazz,
+    /// B-flat minor seven flat five
+    Long,
+}
+
+impl ChordSymbols {
+    /// Returns the name of the style, as stored in the database.
+    pub fn name(self) -> &'static str {
+        match self {
+            ChordSymbols::Pop => "pop",
+            ChordSymbols::Jazz => "jazz",
+            ChordSymbols::Long => "long",
         }
+    }
+}
 
-        Bb | Ash => {
-            if flats {
-                "Bb"
-            } else {
-                "A#"
-            }
+impl std::str::FromStr for ChordSymbols {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ChordSymbols, String> {
+        match s {
+            "pop" => Ok(ChordSymbols::Pop),
+            "jazz" => Ok(ChordSymbols::Jazz),
+            "long" => Ok(ChordSymbols::Long),
+            _ => Err(format!("Unknown style of chord symbols: {}", s)),
         }
-    };
-    s.to_string()
+    }
 }
 
-impl fmt::Display for Chord {
+/// Describes how notes and chords are written down. Every formatting
+/// function takes one of these explicitly, so that several threads can
+/// render the same chord in different ways.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct NotationStyle {
+    /// Accidental used for notes which have no context to be spelled from,
+    /// such as transposed roots, or every note if `spell_by_degree` is false.
+    pub accidentals: Accidentals,
+    /// Spell chord tones according to their scale degree: the third of F# is
+    /// A#, even when notating accidentals using flats.
+    pub spell_by_degree: bool,
+    /// Write the octave number after each note, e.g. `C4` rather than `C`.
+    pub octaves: bool,
+    /// Use the ♯ and ♭ glyphs instead of # and b.
+    pub unicode: bool,
+    /// Style of chord symbols used when naming chords.
+    pub symbols: ChordSymbols,
+}
+
+impl Default for NotationStyle {
+    fn default() -> NotationStyle {
+        NotationStyle {
+            accidentals: Accidentals::Sharps,
+            spell_by_degree: true,
+            octaves: true,
+            unicode: false,
+            symbols: ChordSymbols::Pop,
+        }
+    }
+}
+
+impl NotationStyle {
+    /// Returns the same note as `letter`, written with a sharp or a flat
+    /// according to `accidentals`.
+    pub fn respell_letter(&self, letter: Letter) -> Letter {
+        use pitch_calc::Letter::*;
+        let flats = self.accidentals == Accidentals::Flats;
+        match (letter, flats) {
+            (Csh, true) | (Db, true) => Db,
+            (Dsh, true) | (Eb, true) => Eb,
+            (Fsh, true) | (Gb, true) => Gb,
+            (Gsh, true) | (Ab, true) => Ab,
+            (Ash, true) | (Bb, true) => Bb,
+            (Csh, false) | (Db, false) => Csh,
+            (Dsh, false) | (Eb, false) => Dsh,
+            (Fsh, false) | (Gb, false) => Fsh,
+            (Gsh, false) | (Ab, false) => Gsh,
+            (Ash, false) | (Bb, false) => Ash,
+            (natural, _) => natural,
+        }
+    }
+
+    /// Writes down a spelled note, without an octave.
+    pub fn note_to_string(&self, note: SpelledNote) -> String {
+        let symbol = match (note.accidental < 0, self.unicode) {
+            (true, false) => "b",
+            (true, true) => "♭",
+            (false, false) => "#",
+            (false, true) => "♯",
+        };
+        format!(
+            "{:?}{}",
+            note.natural,
+            symbol.repeat(note.accidental.unsigned_abs() as usize)
+        )
+    }
+
+    /// Writes down a spelled note, followed by its octave if octaves are
+    /// being displayed.
+    pub fn note_octave_to_string(&self, note: SpelledNote, octave: Octave) -> String {
+        if self.octaves {
+            format!("{}{}", self.note_to_string(note), octave)
+        } else {
+            self.note_to_string(note)
+        }
+    }
+}
+
+pub fn letter_to_string(letter: Letter, style: &NotationStyle) -> String {
+    style.note_to_string(SpelledNote::from_letter(style.respell_letter(letter)))
+}
+
+/// Writes a list of notes according to a `NotationStyle`. Returned by
+/// `Chord::display` and `Scale::display`.
+pub struct NotesDisplay<'a> {
+    spelled: Vec<(SpelledNote, Octave)>,
+    notes: Vec<LetterOctave>,
+    style: &'a NotationStyle,
+}
+
+impl<'a> NotesDisplay<'a> {
+    /// Writes any list of spelled notes, each along with the octave it is
+    /// written in.
+    pub fn new(spelled: Vec<(SpelledNote, Octave)>, style: &'a NotationStyle) -> NotesDisplay<'a> {
+        let notes = spelled.iter().map(|(n, o)| n.letter_octave(*o)).collect();
+        NotesDisplay {
+            spelled,
+            notes,
+            style,
+        }
+    }
+}
+
+impl<'a> fmt::Display for NotesDisplay<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let notes = self.notes();
+        let style = self.style;
+        let notes: Vec<String> = if style.spell_by_degree {
+            self.spelled
+                .iter()
+                .map(|(note, octave)| style.note_octave_to_string(*note, *octave))
+                .collect()
+        } else {
+            self.notes
+                .iter()
+                .map(|n| {
+                    let note = SpelledNote::from_letter(style.respell_letter(n.letter()));
+                    style.note_octave_to_string(note, n.octave())
+                })
+                .collect()
+        };
 
-        let output: String = notes
-            .into_iter()
-            .map(|t| format!("{}{} ", letter_to_string(t.letter()), t.octave()))
-            .collect();
+        write!(f, "{}", notes.join(" "))
+    }
+}
 
-        write!(f, "{}", output.trim())
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display(&NotationStyle::default()))
+    }
+}
+
+impl fmt::Display for Scale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display(&NotationStyle::default()))
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
     #[allow(unused_imports)]
     use pitch_calc::Letter::*;
 
+    /// Returns the chord built on `root` in the fourth octave, in root
+    /// position.
+    pub(crate) fn chord(root: Letter, quality: Quality) -> Chord {
+        Chord::new(LetterOctave(root, 4), quality)
+    }
+
     #[test]
     fn interval_maths() {
         use intervals::*;
@@ -256,23 +1076,78 @@ mod tests {
         assert_eq!(C - Min2nd, C + Maj7th);
     }
 
+    #[test]
+    fn interval_type() {
+        use intervals::*;
+        use IntervalQuality::*;
+        // Intervals which sound the same are still told apart.
+        assert_eq!(Aug5th.semitones(), Min6th.semitones());
+        assert_ne!(Aug5th, Min6th);
+        assert_eq!(Dim7th.semitones(), 9);
+        assert_eq!(Maj13th.semitones(), 21);
+
+        let names: Vec<String> = [Min3rd, Per5th, Aug4th, Maj9th, Dim7th, Root]
+            .iter()
+            .map(|i| i.to_string())
+            .collect();
+        assert_eq!(names, vec!["m3", "P5", "A4", "M9", "d7", "P1"]);
+        assert_eq!("m3".parse(), Ok(Min3rd));
+        assert_eq!("A11".parse(), Ok(Aug11th));
+        assert_eq!("dd7".parse::<Interval>().map(|i| i.semitones()), Ok(8));
+        assert!("M5".parse::<Interval>().is_err());
+        assert!("P3".parse::<Interval>().is_err());
+        assert!("x".parse::<Interval>().is_err());
+
+        assert_eq!(Maj3rd + Min3rd, Per5th);
+        assert_eq!(Per5th - Maj3rd, Min3rd);
+        assert_eq!(Maj3rd + Maj3rd, Aug5th);
+        assert_eq!(Min3rd + Min3rd + Min3rd, Dim7th);
+        assert_eq!(Per5th + Per5th, Maj9th);
+        assert_eq!(Maj3rd.inverted(), Min6th);
+        assert_eq!(Aug4th.inverted(), Dim5th);
+        assert_eq!(Maj9th.inverted(), Min7th);
+        assert_eq!(Octave.inverted(), Root);
+        assert!(Min9th.is_compound());
+        assert_eq!(Min9th.simple(), Min2nd);
+        assert_eq!(Maj3rd.altered(-1), Min3rd);
+        assert_eq!(
+            Interval::from_semitones(4, 4),
+            Interval::new(4, Diminished(1))
+        );
+        assert_eq!(
+            Interval::from_semitones(6, 7),
+            Interval::new(6, Diminished(1))
+        );
+
+        // Notes are spelled according to the interval.
+        assert_eq!(transpose(LetterOctave(C, 4), Aug5th), LetterOctave(Gsh, 4));
+        assert_eq!(transpose(LetterOctave(C, 4), Min6th), LetterOctave(Ab, 4));
+        assert_eq!(transpose(LetterOctave(B, 4), Min9th), LetterOctave(C, 6));
+        assert_eq!(Db + Maj3rd, F);
+        assert_eq!(Db + Aug4th, G);
+        assert_eq!(C - Maj3rd, Ab);
+    }
+
     #[test]
     #[allow(non_snake_case)]
     fn chord_maths() {
-        use degree_intervals::*;
+        use intervals::*;
         let Cmaj = Chord {
             root: LetterOctave(Letter::C, 4),
             quality: vec![Maj3rd, Per5th],
+            bass: None,
         };
 
         let Gmaj = Chord {
             root: LetterOctave(Letter::G, 4),
             quality: vec![Maj3rd, Per5th],
+            bass: None,
         };
 
         let Amin = Chord {
             root: LetterOctave(Letter::A, 4),
             quality: vec![Min3rd, Per5th],
+            bass: None,
         };
 
         assert_eq!(Gmaj, Cmaj.transposed(intervals::Per5th));
@@ -290,4 +1165,196 @@ mod tests {
             .collect();
         assert_eq!(Cmaj.notes(), notes_of_c_major);
     }
+
+    #[test]
+    fn inversions() {
+        use intervals::*;
+        let cmaj7 = Chord {
+            root: LetterOctave(Letter::C, 4),
+            quality: vec![Maj3rd, Per5th, Maj7th],
+            bass: None,
+        };
+
+        assert_eq!(cmaj7.inverted(0), Some(cmaj7.clone()));
+        assert_eq!(cmaj7.inverted(4), None);
+        assert_eq!(cmaj7.inverted(1).unwrap().bass, Some(Letter::E));
+        assert_eq!(cmaj7.inverted(1).unwrap().to_string(), "E4 G4 B4 C5");
+        assert_eq!(cmaj7.inverted(2).unwrap().to_string(), "G4 B4 C5 E5");
+        assert_eq!(cmaj7.inverted(3).unwrap().to_string(), "B4 C5 E5 G5");
+
+        // The bass keeps the spelling of the chord tone.
+        let ebm = Chord {
+            root: LetterOctave(Letter::Eb, 4),
+            quality: vec![Min3rd, Per5th],
+            bass: None,
+        };
+        assert_eq!(ebm.inverted(1).unwrap().to_string(), "Gb4 Bb4 Eb5");
+
+        // Slash chords: a chord tone in the bass is an inversion, any other
+        // note is played below the root.
+        let am7 = Chord {
+            root: LetterOctave(Letter::A, 3),
+            quality: vec![Min3rd, Per5th, Min7th],
+            bass: Some(Letter::G),
+        };
+        assert_eq!(am7.to_string(), "G4 A4 C5 E5");
+
+        let c_over_d = Chord {
+            root: LetterOctave(Letter::C, 4),
+            quality: vec![Maj3rd, Per5th],
+            bass: Some(Letter::D),
+        };
+        assert_eq!(c_over_d.to_string(), "D3 C4 E4 G4");
+        assert_eq!(c_over_d.transposed(intervals::Per5th).bass, Some(Letter::A));
+        assert_eq!(
+            c_over_d.with_root_letter(Letter::F).to_string(),
+            "G3 F4 A4 C5"
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn note_spelling() {
+        use intervals::*;
+        let spelled = |root: Letter, quality: Quality| chord(root, quality).to_string();
+
+        assert_eq!(spelled(Fsh, vec![Min3rd, Dim5th]), "F#4 A4 C5");
+        assert_eq!(spelled(Eb, vec![Min3rd, Per5th]), "Eb4 Gb4 Bb4");
+        assert_eq!(spelled(Gsh, vec![Maj3rd, Aug5th]), "G#4 B#4 D##5");
+        assert_eq!(
+            spelled(Db, vec![Min3rd, Dim5th, Dim7th]),
+            "Db4 Fb4 Abb4 Cbb5"
+        );
+        assert_eq!(spelled(B, vec![Maj3rd, Per5th, Octave]), "B4 D#5 F#5 B5");
+
+        let Cmaj7 = Chord {
+            root: LetterOctave(C, 5),
+            quality: vec![Maj3rd, Per5th, Maj7th],
+            bass: None,
+        };
+        assert_eq!(Cmaj7.transposed(Per4th).to_string(), "F5 A5 C6 E6");
+        assert_eq!(Cmaj7.transposed(Per5th).to_string(), "G5 B5 D6 F#6");
+        assert_eq!(
+            Cmaj7.transposed_semitones(-Per5th.semitones()).to_string(),
+            "F4 A4 C5 E5"
+        );
+
+        // Transposing by an interval keeps the spelling, where the same
+        // number of semitones would fall back on sharps.
+        let Ebmin = chord(Eb, vec![Min3rd, Per5th]);
+        assert_eq!(Ebmin.transposed(Min3rd).to_string(), "Gb4 Bbb4 Db5");
+        assert_eq!(
+            Ebmin.transposed_semitones(Min3rd.semitones()).root(),
+            LetterOctave(Fsh, 4)
+        );
+        let eb_over_bb = Ebmin.inverted(2).unwrap();
+        assert_eq!(eb_over_bb.transposed(Min3rd).bass, Some(Db));
+    }
+
+    #[test]
+    fn spelled_note_maths() {
+        let f_sharp = SpelledNote::from_letter(Fsh);
+        assert_eq!(f_sharp.to_string(), "F#");
+        assert_eq!(f_sharp.letter(), Fsh);
+        assert_eq!(f_sharp.above(intervals::Maj3rd).to_string(), "A#");
+        assert_eq!(f_sharp.above(intervals::Dim5th).to_string(), "C");
+
+        let c_flat = SpelledNote::new(Natural::C, -1);
+        assert_eq!(c_flat.letter(), B);
+        assert_eq!(c_flat.semitones(), -1);
+    }
+
+    #[test]
+    fn scales() {
+        use intervals::*;
+        let d_dorian = Scale {
+            root: LetterOctave(D, 4),
+            pattern: vec![Maj2nd, Min3rd, Per4th, Per5th, Maj6th, Min7th],
+        };
+        assert_eq!(d_dorian.to_string(), "D4 E4 F4 G4 A4 B4 C5");
+        assert!(d_dorian.contains(B));
+        assert!(!d_dorian.contains(Bb));
+
+        let e_dorian = d_dorian.transposed(intervals::Maj2nd);
+        assert_eq!(e_dorian.root(), LetterOctave(E, 4));
+        assert_eq!(e_dorian.to_string(), "E4 F#4 G4 A4 B4 C#5 D5");
+        assert_eq!(e_dorian, d_dorian.with_root(LetterOctave(E, 4)));
+        assert_eq!(e_dorian.notes().len(), 7);
+
+        let eb_lydian = Scale {
+            root: LetterOctave(Eb, 4),
+            pattern: vec![Maj2nd, Maj3rd, Aug4th, Per5th, Maj6th, Maj7th],
+        };
+        assert_eq!(eb_lydian.to_string(), "Eb4 F4 G4 A4 Bb4 C5 D5");
+    }
+
+    #[test]
+    fn harmonization() {
+        use intervals::*;
+        let chords = Key::major(Eb).diatonic_chords(ChordSize::Seventh);
+        let roots: Vec<Letter> = chords.iter().map(|c| c.root_letter()).collect();
+        assert_eq!(roots, vec![Eb, F, G, Ab, Bb, C, D]);
+        assert_eq!(chords[0].quality, vec![Maj3rd, Per5th, Maj7th]);
+        assert_eq!(chords[1].quality, vec![Min3rd, Per5th, Min7th]);
+        assert_eq!(chords[4].quality, vec![Maj3rd, Per5th, Min7th]);
+        assert_eq!(chords[6].quality, vec![Min3rd, Dim5th, Min7th]);
+        assert_eq!(chords[3].to_string(), "Ab4 C5 Eb5 G5");
+        assert_eq!(chords[6].to_string(), "D5 F5 Ab5 C6");
+
+        let triads = Key::minor(A).diatonic_chords(ChordSize::Triad);
+        assert_eq!(triads[1].quality, vec![Min3rd, Dim5th]);
+        assert_eq!(triads[1].to_string(), "B4 D5 F5");
+
+        let ninths = Key::major(C).diatonic_chords(ChordSize::Ninth);
+        assert_eq!(ninths[0].quality, vec![Maj3rd, Per5th, Maj7th, Maj9th]);
+        assert_eq!(ninths[2].quality, vec![Min3rd, Per5th, Min7th, Min9th]);
+        assert_eq!(ninths[6].to_string(), "B4 D5 F5 A5 C6");
+
+        // Chords are only stacked in thirds on scales of seven notes.
+        let pentatonic = Scale {
+            root: LetterOctave(C, 4),
+            pattern: vec![Maj2nd, Maj3rd, Per5th, Maj6th],
+        };
+        assert_eq!(pentatonic.harmonize(ChordSize::Seventh), None);
+
+        assert!(Key::minor(A).is_minor());
+        assert!(!Key::major(A).is_minor());
+        assert_eq!(Key::major(Bb).tonic(), Bb);
+        assert_eq!(Key::major(Bb).degree(2, 0).letter(), C);
+        assert_eq!(Key::major(C).degree(6, -1).letter(), Ab);
+        assert_eq!(Key::minor(E).degree(7, 1).to_string(), "D#");
+    }
+
+    #[test]
+    fn notation_styles() {
+        use intervals::*;
+        let chord = Chord {
+            root: LetterOctave(Fsh, 4),
+            quality: vec![Maj3rd, Per5th],
+            bass: None,
+        };
+
+        let flats = NotationStyle {
+            accidentals: Accidentals::Flats,
+            ..NotationStyle::default()
+        };
+        assert_eq!(chord.display(&flats).to_string(), "F#4 A#4 C#5");
+
+        let by_letter = NotationStyle {
+            spell_by_degree: false,
+            ..flats
+        };
+        assert_eq!(chord.display(&by_letter).to_string(), "Gb4 Bb4 Db5");
+
+        let unicode = NotationStyle {
+            octaves: false,
+            unicode: true,
+            ..NotationStyle::default()
+        };
+        assert_eq!(chord.display(&unicode).to_string(), "F♯ A♯ C♯");
+
+        assert_eq!(letter_to_string(Ash, &flats), "Bb");
+        assert_eq!(letter_to_string(Bb, &NotationStyle::default()), "A#");
+        assert_eq!(flats.respell_letter(Csh), Db);
+    }
 }
@@ -0,0 +1,171 @@
+use crate::identify::{identify, Identification};
+use crate::music_theory::*;
+use crate::progression::{Beats, Progression};
+use diesel::sqlite::SqliteConnection;
+
+/// The axis notes are mirrored around in negative harmony. It lies halfway
+/// between two notes, which swap places when mirrored: the axis of C major
+/// lies between E and Eb, so C and G swap places, as do D and F.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Axis {
+    /// Sum of the natural notes of the two notes, counted from C as 0 up to
+    /// 6. A note and its reflection always add up to the same sum.
+    naturals: i32,
+    /// Sum of the two notes in semitones above C, up to 11.
+    semitones: Semitones,
+}
+
+impl Axis {
+    /// Returns the axis which swaps `a` and `b`.
+    pub fn between(a: SpelledNote, b: SpelledNote) -> Axis {
+        Axis {
+            naturals: (a.natural.index() + b.natural.index()).rem_euclid(7),
+            semitones: (a.semitones() + b.semitones()).rem_euclid(12),
+        }
+    }
+
+    /// Returns the axis which swaps the tonic and the dominant of the key.
+    pub fn of_key(key: &Key) -> Axis {
+        Axis::between(key.degree(degrees::I, 0), key.degree(degrees::V, 0))
+    }
+
+    /// Mirrors the note around the axis. The spelling is mirrored too: in C,
+    /// B becomes Ab rather than G#.
+    pub fn reflect(&self, note: SpelledNote) -> SpelledNote {
+        let natural = Natural::from_index(self.naturals - note.natural.index());
+        let difference = (self.semitones - note.semitones() - natural.semitones()).rem_euclid(12);
+        let accidental = if difference > 6 {
+            difference - 12
+        } else {
+            difference
+        };
+        SpelledNote::new(natural, accidental)
+    }
+}
+
+/// Mirrors every note of the chord around the axis. The lowest note becomes
+/// the highest, so the result is reversed to list the notes from the lowest
+/// upwards once more.
+pub fn negative_notes(chord: &Chord, axis: &Axis) -> Vec<SpelledNote> {
+    chord
+        .spelled_notes()
+        .iter()
+        .rev()
+        .map(|(note, _)| axis.reflect(*note))
+        .collect()
+}
+
+/// Names the negative of the chord in every way it can be named, most
+/// plausible first: the negative of G7 in C is Dm7b5, also read as Fm6/D.
+pub fn negative_readings(
+    chord: &Chord,
+    axis: &Axis,
+    conn: &SqliteConnection,
+) -> Vec<Identification> {
+    let letters: Vec<Letter> = negative_notes(chord, axis)
+        .iter()
+        .map(|note| note.letter())
+        .collect();
+    identify(&letters, conn)
+}
+
+impl Chord {
+    /// Returns the negative of the chord, mirrored around the axis and named
+    /// in the most plausible way, with its root in the same octave. Returns
+    /// `None` if the mirrored notes do not make up any chord in the
+    /// database.
+    pub fn negative(&self, axis: &Axis, conn: &SqliteConnection) -> Option<Chord> {
+        let reading = negative_readings(self, axis, conn).into_iter().next()?;
+        Some(reading.chord.with_root(LetterOctave(
+            reading.chord.root_letter(),
+            self.root.octave(),
+        )))
+    }
+}
+
+impl Progression {
+    /// Returns the negative of every chord of the progression, each held for
+    /// as long as the chord it replaces. Returns `None` if any chord has no
+    /// negative in the database.
+    pub fn negative(&self, axis: &Axis, conn: &SqliteConnection) -> Option<Progression> {
+        let chords = self
+            .chords
+            .iter()
+            .map(|(chord, beats)| Some((chord.negative(axis, conn)?, *beats)))
+            .collect::<Option<Vec<(Chord, Beats)>>>()?;
+        Some(Progression {
+            chords,
+            time: self.time,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chord_library;
+    use crate::database::initialise_database;
+    use crate::music_theory::tests::chord;
+    use crate::progression::TimeSignature;
+    use intervals::*;
+    use Letter::*;
+
+    #[test]
+    fn reflections() {
+        let axis = Axis::of_key(&Key::major(C));
+        let reflect = |letter| axis.reflect(SpelledNote::from_letter(letter)).letter();
+        assert_eq!(reflect(C), G);
+        assert_eq!(reflect(E), Eb);
+        assert_eq!(reflect(B), Ab);
+        assert_eq!(reflect(Fsh), Db);
+
+        let dominant = chord(G, vec![Maj3rd, Per5th, Min7th]);
+        let notes: Vec<Letter> = negative_notes(&dominant, &axis)
+            .iter()
+            .map(|n| n.letter())
+            .collect();
+        assert_eq!(notes, vec![D, F, Ab, C]);
+
+        // The axis can lie anywhere, e.g. between G and G# in E minor.
+        let axis = Axis::of_key(&Key::minor(E));
+        assert_eq!(axis.reflect(SpelledNote::from_letter(E)).letter(), B);
+        assert_eq!(axis.reflect(SpelledNote::from_letter(G)).letter(), Gsh);
+    }
+
+    #[test]
+    fn negative_chords() {
+        let conn = initialise_database().unwrap();
+        chord_library::populate_database(&conn);
+        let style = NotationStyle::default();
+        let axis = Axis::of_key(&Key::major(C));
+        let negative = |c: Chord| c.negative(&axis, &conn).unwrap().symbol(&style, &conn);
+
+        assert_eq!(negative(chord(G, vec![Maj3rd, Per5th, Min7th])), "Dm7b5");
+        assert_eq!(negative(chord(C, vec![Maj3rd, Per5th])), "Cm");
+        assert_eq!(negative(chord(F, vec![Maj3rd, Per5th])), "Gm");
+        assert_eq!(negative(chord(D, vec![Min3rd, Per5th, Min7th])), "Gm7");
+
+        let readings: Vec<String> =
+            negative_readings(&chord(G, vec![Maj3rd, Per5th, Min7th]), &axis, &conn)
+                .iter()
+                .map(|r| r.chord.symbol(&style, &conn))
+                .collect();
+        assert!(readings.contains(&"Fm6/D".to_owned()));
+
+        let progression = Progression {
+            chords: vec![
+                (chord(D, vec![Min3rd, Per5th, Min7th]), 4),
+                (chord(G, vec![Maj3rd, Per5th, Min7th]), 4),
+                (chord(C, vec![Maj3rd, Per5th, Maj7th]), 8),
+            ],
+            time: TimeSignature::default(),
+        };
+        let negative = progression.negative(&axis, &conn).unwrap();
+        let beats: Vec<Beats> = negative.chords.iter().map(|(_, b)| *b).collect();
+        assert_eq!(beats, vec![4, 4, 8]);
+        assert_eq!(
+            negative.lead_sheet(&style, &conn),
+            "| Gm7    | Dm7b5  | Abmaj7 | .      |"
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use crate::music_theory::*;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// The Neo-Riemannian transforms, each of which turns a major triad into a
+/// minor one or back by moving a single note.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Transform {
+    /// Parallel: C major and C minor. The third moves by a half step.
+    P,
+    /// Leading-tone exchange: C major and E minor. The root of the major
+    /// triad moves down a half step.
+    L,
+    /// Relative: C major and A minor. The fifth of the major triad moves up
+    /// a whole step.
+    R,
+}
+
+impl Transform {
+    /// Reads a compound transform such as "PLR", which is applied from left
+    /// to right. Returns `None` if any letter is not P, L or R.
+    pub fn parse_compound(input: &str) -> Option<Vec<Transform>> {
+        input
+            .chars()
+            .map(|c| match c {
+                'P' => Some(Transform::P),
+                'L' => Some(Transform::L),
+                'R' => Some(Transform::R),
+                _ => None,
+            })
+            .collect::<Option<Vec<Transform>>>()
+            .filter(|transforms| !transforms.is_empty())
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// The position of a triad on the Tonnetz, the grid of notes in which
+/// moving right goes up a perfect fifth and moving up goes up a major third.
+/// Every triad is a triangle on the grid; major triads point up and minor
+/// triads point down.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct TonnetzPosition {
+    /// Perfect fifths from C to the root, from 0 to 3.
+    pub fifths: i32,
+    /// Major thirds from C to the root, from 0 to 2.
+    pub thirds: i32,
+    pub major: bool,
+}
+
+/// Returns true if the chord is a major triad, false if it is a minor
+/// triad, and `None` if it is neither.
+fn is_major_triad(chord: &Chord) -> Option<bool> {
+    use intervals::*;
+    if chord.quality == vec![Maj3rd, Per5th] {
+        Some(true)
+    } else if chord.quality == vec![Min3rd, Per5th] {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+impl Chord {
+    /// Applies a Neo-Riemannian transform to the chord, which must be a major
+    /// or minor triad. The result is in root position, with its root in the
+    /// same octave. Returns `None` for any other chord.
+    pub fn transformed(&self, transform: Transform) -> Option<Chord> {
+        use intervals::*;
+        let major = is_major_triad(self)?;
+        let root = SpelledNote::from_letter(self.root_letter());
+        let (new_root, quality) = match (transform, major) {
+            (Transform::P, true) => (root, vec![Min3rd, Per5th]),
+            (Transform::P, false) => (root, vec![Maj3rd, Per5th]),
+            (Transform::L, true) => (root.above(Maj3rd), vec![Min3rd, Per5th]),
+            (Transform::L, false) => (root.above(Min6th), vec![Maj3rd, Per5th]),
+            (Transform::R, true) => (root.above(Maj6th), vec![Min3rd, Per5th]),
+            (Transform::R, false) => (root.above(Min3rd), vec![Maj3rd, Per5th]),
+        };
+        Some(
+            self.with_bass(None)
+                .with_root_letter(new_root.letter())
+                .with_quality(quality),
+        )
+    }
+
+    /// Applies the transforms one after the other, returning the chord after
+    /// each step. Returns `None` if the chord is not a major or minor triad.
+    pub fn transformed_by(&self, transforms: &[Transform]) -> Option<Vec<Chord>> {
+        let mut chords: Vec<Chord> = vec![];
+        for transform in transforms {
+            let chord = chords.last().unwrap_or(self).transformed(*transform)?;
+            chords.push(chord);
+        }
+        Some(chords)
+    }
+
+    /// Returns the position of the chord on the Tonnetz, if it is a major or
+    /// minor triad.
+    pub fn tonnetz(&self) -> Option<TonnetzPosition> {
+        let major = is_major_triad(self)?;
+        let pitch = pitch_class(self.root_letter());
+        (0..4)
+            .flat_map(|fifths| (0..3).map(move |thirds| (fifths, thirds)))
+            .find(|(fifths, thirds)| (7 * fifths + 4 * thirds) % 12 == pitch)
+            .map(|(fifths, thirds)| TonnetzPosition {
+                fifths,
+                thirds,
+                major,
+            })
+    }
+}
+
+/// Returns the fewest transforms which turn the triad `from` into the triad
+/// `to`, or `None` if either is not a major or minor triad. Triads with the
+/// same notes need no transforms at all.
+pub fn shortest_path(from: &Chord, to: &Chord) -> Option<Vec<Transform>> {
+    let target = to.tonnetz()?;
+
+    // Breadth-first search over the 24 triads.
+    let mut seen = vec![from.tonnetz()?];
+    let mut queue = VecDeque::new();
+    queue.push_back((from.clone(), vec![]));
+    while let Some((chord, path)) = queue.pop_front() {
+        if chord.tonnetz() == Some(target) {
+            return Some(path);
+        }
+        for transform in &[Transform::P, Transform::L, Transform::R] {
+            let next = chord.transformed(*transform)?;
+            let position = next.tonnetz()?;
+            if !seen.contains(&position) {
+                seen.push(position);
+                let mut next_path = path.clone();
+                next_path.push(*transform);
+                queue.push_back((next, next_path));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music_theory::tests::chord;
+    use intervals::*;
+    use Letter::*;
+
+    fn triad(root: Letter, major: bool) -> Chord {
+        let third = if major { Maj3rd } else { Min3rd };
+        chord(root, vec![third, Per5th])
+    }
+
+    #[test]
+    fn transforms() {
+        use Transform::*;
+        let c = triad(C, true);
+        assert_eq!(c.transformed(P), Some(triad(C, false)));
+        assert_eq!(c.transformed(L), Some(triad(E, false)));
+        assert_eq!(c.transformed(R), Some(triad(A, false)));
+        assert_eq!(triad(E, false).transformed(L), Some(c.clone()));
+        assert_eq!(triad(A, false).transformed(R), Some(c.clone()));
+
+        // Every transform undoes itself.
+        for t in &[P, L, R] {
+            let once = c.transformed(*t).unwrap();
+            assert_eq!(once.transformed(*t), Some(c.clone()));
+        }
+
+        let steps = c.transformed_by(&Transform::parse_compound("LPR").unwrap());
+        assert_eq!(
+            steps,
+            Some(vec![triad(E, false), triad(E, true), triad(Csh, false)])
+        );
+        assert_eq!(Transform::parse_compound("PLX"), None);
+        assert_eq!(Transform::parse_compound(""), None);
+
+        let c7 = c.with_quality(vec![Maj3rd, Per5th, Min7th]);
+        assert_eq!(c7.transformed(P), None);
+        assert_eq!(c7.tonnetz(), None);
+    }
+
+    #[test]
+    fn tonnetz() {
+        let position = |root, major| triad(root, major).tonnetz().unwrap();
+        assert_eq!(
+            position(C, true),
+            TonnetzPosition {
+                fifths: 0,
+                thirds: 0,
+                major: true
+            }
+        );
+        assert_eq!((position(G, true).fifths, position(G, true).thirds), (1, 0));
+        assert_eq!(
+            (position(E, false).fifths, position(E, false).thirds),
+            (0, 1)
+        );
+        assert_eq!((position(F, true).fifths, position(F, true).thirds), (3, 2));
+    }
+
+    #[test]
+    fn shortest_paths() {
+        use Transform::*;
+        let c = triad(C, true);
+        assert_eq!(shortest_path(&c, &c), Some(vec![]));
+        assert_eq!(shortest_path(&c, &triad(A, false)), Some(vec![R]));
+        assert_eq!(shortest_path(&c, &triad(E, true)), Some(vec![L, P]));
+
+        // The hexatonic pole is three transforms away.
+        let path = shortest_path(&c, &triad(Ab, false)).unwrap();
+        assert_eq!(path.len(), 3);
+        assert_eq!(
+            c.transformed_by(&path).unwrap().last().unwrap().tonnetz(),
+            triad(Ab, false).tonnetz()
+        );
+        assert_eq!(
+            shortest_path(&c, &c.with_quality(vec![Maj3rd, Aug5th])),
+            None
+        );
+    }
+}
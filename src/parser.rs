@@ -1,6 +1,12 @@
+use crate::database::get_quality;
 use crate::music_theory::*;
-use nom::character::complete::multispace0;
+use crate::negative_harmony::Axis;
+use crate::neo_riemannian::Transform;
+use crate::progression::TimeSignature;
+use crate::voicing::*;
+use diesel::sqlite::SqliteConnection;
 use nom::character::complete::not_line_ending;
+use nom::character::complete::{multispace0, multispace1};
 use nom::*;
 use std::collections::HashMap;
 use std::str::FromStr;
@@ -11,14 +17,153 @@ use std::str::FromStr;
 pub enum Command {
     /// Nothing at all was typed.
     EmptyString,
-    /// A valid note letter followed by the chord quality.
-    Chord(Letter, String),
+    /// A valid note letter followed by the chord quality and optionally a
+    /// bass note, as in C/E.
+    Chord(ChordSymbol),
     /// The word "sharps"
     Sharps,
     /// The word "flats"
     Flats,
     /// The word "transpose", followed by a signed integer, followed by a chord
-    Transpose(i32, Letter, String),
+    Transpose(i32, ChordSymbol),
+    /// The word "inv", followed by the number of the inversion and a chord
+    Inversion(usize, ChordSymbol),
+    /// The word "scale", followed by the root and the name of the scale
+    Scale(Letter, String),
+    /// The word "diatonic", followed by a scale and optionally the size of
+    /// the chords to build: 3, 7 or 9
+    Diatonic(Letter, String, ChordSize),
+    /// The word "what", followed by a list of notes, lowest first
+    Identify(Vec<Letter>),
+    /// The word "voicing", followed by the name of a voicing
+    Voicing(Voicing),
+    /// The word "lead", followed by a list of chords separated by spaces
+    Lead(Vec<ChordSymbol>),
+    /// The word "symbols", followed by "pop", "jazz" or "long"
+    Symbols(ChordSymbols),
+    /// The words "analyze in", followed by a key, a colon and a list of
+    /// chords separated by spaces
+    Analyze(Key, Vec<ChordSymbol>),
+    /// The word "key", followed by a key such as "Bb" or "C minor"
+    Key(Key),
+    /// A list of roman numerals or Nashville numbers, in the session's key
+    Degrees(Vec<DegreeSymbol>),
+    /// Bars of chords written as on a lead sheet, e.g. `| C . Am . | F G7 |`.
+    /// Empty slots, written as dots, are `None`.
+    Progression(Vec<Vec<Option<ChordSymbol>>>),
+    /// The word "transpose", followed by a signed integer and a progression
+    TransposeProgression(i32, Vec<Vec<Option<ChordSymbol>>>),
+    /// The word "time", followed by a time signature such as 3/4
+    Time(TimeSignature),
+    /// The word "tempo", followed by the number of beats per minute
+    Tempo(u32),
+    /// The word "keys", followed by a progression or a list of chords, which
+    /// are read as one chord per bar
+    Keys(Vec<Vec<Option<ChordSymbol>>>),
+    /// The word "functions" or "cadences", followed by "in", a key, a colon
+    /// and a list of chords separated by spaces
+    Functions(Key, Vec<ChordSymbol>),
+    /// The word "subs", followed by a chord, the word "in" and a key
+    Substitutions(ChordSymbol, Key),
+    /// The word "audition", followed by the number of a substitution listed
+    /// by "subs"
+    Audition(usize),
+    /// The word "nr", followed by a triad and a compound Neo-Riemannian
+    /// transform such as LPR
+    NeoRiemannian(ChordSymbol, Vec<Transform>),
+    /// The word "nr", followed by a triad, the word "to" and another triad
+    NeoRiemannianPath(ChordSymbol, ChordSymbol),
+    /// The word "negative", followed by an axis and a list of chords: "in"
+    /// and a key mirrors them around its tonic and dominant, while "around"
+    /// and two notes mirrors them around the axis between those notes.
+    Negative(Axis, Vec<ChordSymbol>),
+    /// The word "render", followed by the name of a WAV file and optionally
+    /// a command. The notes the command plays are written to the file
+    /// rather than played; without a command, the last one is rendered.
+    Render(String, Box<Command>),
+    /// The word "pause"
+    Pause,
+    /// The word "resume"
+    Resume,
+    /// The word "stop"
+    Stop,
+}
+
+/// A chord as it is written, e.g. Am7/G: the root, the name of its quality
+/// and the bass note, if it is not the root.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ChordSymbol {
+    pub root: Letter,
+    pub quality: String,
+    pub bass: Option<Letter>,
+}
+
+/// Looks up the quality in the database, or builds it from its parts if it
+/// has no name there, e.g. "7b9#11".
+fn lookup_quality(name: &str, conn: &SqliteConnection) -> Option<Quality> {
+    get_quality(name, conn).or_else(|| chord_quality(name))
+}
+
+impl ChordSymbol {
+    /// Looks up the quality of the chord, and returns the chord with its root
+    /// in the given `octave`.
+    pub fn to_chord(&self, octave: Octave, conn: &SqliteConnection) -> Option<Chord> {
+        let quality = lookup_quality(&self.quality, conn);
+        quality.map(|quality| Chord {
+            root: LetterOctave(self.root, octave),
+            quality,
+            bass: self.bass,
+        })
+    }
+}
+
+/// A chord written relative to a key, either as a roman numeral such as
+/// bVII7, V/V or ii65, or as a Nashville number such as 6- or 1/3.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DegreeSymbol {
+    /// The accidental and degree of the root, e.g. (-1, 6) for bVI.
+    pub root: (Accidental, Degree),
+    /// The name of the chord's quality, e.g. "m7".
+    pub quality: String,
+    /// Which chord tone is in the bass, as given by figures such as 65.
+    pub inversion: usize,
+    /// The degree in the bass, for Nashville numbers such as 1/3.
+    pub bass: Option<(Accidental, Degree)>,
+    /// The degree the chord belongs to, for secondary chords such as V/V.
+    pub of: Option<(Accidental, Degree)>,
+}
+
+impl DegreeSymbol {
+    /// Works out the chord in the given `key`, with its root in `octave`.
+    /// Secondary chords are built on the degree of the major key of the
+    /// chord they lead to: V/ii in C is A major. In minor keys, diminished
+    /// chords on the sixth and seventh degrees are built on the raised
+    /// degree, so vii°7 in A minor is G#dim7.
+    pub fn to_chord(&self, key: &Key, octave: Octave, conn: &SqliteConnection) -> Option<Chord> {
+        let (accidental, degree) = self.root;
+        let quality = lookup_quality(&self.quality, conn)?;
+        let raised = key.is_minor()
+            && self.of.is_none()
+            && (degree == degrees::VI || degree == degrees::VII)
+            && quality.contains(&intervals::Dim5th);
+        let root = match self.of {
+            Some((of_accidental, of)) => {
+                Key::major(key.degree(of, of_accidental).letter()).degree(degree, accidental)
+            }
+            None if raised => key.degree(degree, accidental + 1),
+            None => key.degree(degree, accidental),
+        };
+
+        let chord = Chord {
+            root: LetterOctave(root.letter(), octave),
+            quality,
+            bass: self.bass.map(|(a, d)| key.degree(d, a).letter()),
+        };
+        match self.inversion {
+            0 => Some(chord),
+            inversion => chord.inverted(inversion),
+        }
+    }
 }
 
 // Parsers & sub-parsers for Chord.
@@ -69,17 +214,245 @@ fn note_map() -> HashMap<String, Letter> {
 }
 
 named! { pub letter (&str) -> Letter,
-    map_opt!(select_letter, |s: String| note_map().get(&s).map(|l| l.clone()))
+    map_opt!(select_letter, |s: String| note_map().get(&s).copied())
+}
+
+/// Splits the bass note off the end of a chord's quality. Only the part
+/// after the last slash is considered, and only if it is a note, so that
+/// qualities such as "6/9" are left alone.
+fn quality_and_bass(input: &str) -> (String, Option<Letter>) {
+    let input = input.trim();
+    match input.rsplit_once('/') {
+        Some((quality, bass)) => match letter(bass.trim()) {
+            Ok(("", bass)) => (quality.trim().to_owned(), Some(bass)),
+            _ => (input.to_owned(), None),
+        },
+        None => (input.to_owned(), None),
+    }
+}
+
+named! { pub chord_symbol (&str) -> ChordSymbol,
+    do_parse!(
+        root: letter >>
+        rest: not_line_ending >>
+        ({
+            let (quality, bass) = quality_and_bass(rest);
+            ChordSymbol { root, quality, bass }
+        })
+    )
 }
 
 named! { pub command_chord (&str) -> Command,
+    map!(chord_symbol, Command::Chord)
+}
+
+// Parsers for the parts of a chord's quality, such as "m", "7", "sus4" or
+// "#11", which are combined into a `Quality` by `chord_quality`.
+
+/// One part of the quality of a chord symbol.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Modifier {
+    /// m, min, -
+    Minor,
+    /// maj, M: the seventh is major, if there is one
+    Major,
+    /// Δ: major seventh, even if no seventh is written
+    Delta,
+    /// dim, °: the seventh is diminished, if there is one
+    Diminished,
+    /// ø: half-diminished seventh
+    HalfDiminished,
+    /// aug, +
+    Augmented,
+    /// 5: root and fifth only
+    Power,
+    /// 6
+    Sixth,
+    /// 6/9, 69
+    SixNine,
+    /// 7, 9, 11 or 13, along with the extensions below it
+    Extension(Degree),
+    /// sus2, sus4: the third replaced by the given degree
+    Suspended(Degree),
+    /// add9, add#11: a single note, without the ones below it
+    Added(Accidental, Degree),
+    /// b5, #9, b13: replaces the unaltered note of the same degree
+    Altered(Accidental, Degree),
+    /// no3, omit5
+    Omitted(Degree),
+    /// alt: altered dominant, with b9, #9, #11 and b13 instead of the fifth
+    Alt,
+    /// Brackets, commas and spaces are ignored.
+    Separator,
+}
+
+named! { accidental (&str) -> Accidental,
+    alt!(
+        map!(one_of!("b♭"), |_| -1) |
+        map!(one_of!("#♯"), |_| 1)
+    )
+}
+
+named! { modifier_degree (&str) -> Degree,
+    map_res!(nom::character::complete::digit1, Degree::from_str)
+}
+
+named! { modifier_added (&str) -> Modifier,
     do_parse!(
-        letter: letter >>
-        chord: not_line_ending >>
-        (Command::Chord(letter, chord.trim().to_string()))
+        complete!(tag!("add")) >>
+        accidental: opt!(complete!(accidental)) >>
+        degree: modifier_degree >>
+        (Modifier::Added(accidental.unwrap_or(0), degree))
     )
 }
 
+named! { modifier_altered (&str) -> Modifier,
+    do_parse!(
+        accidental: accidental >>
+        degree: modifier_degree >>
+        (Modifier::Altered(accidental, degree))
+    )
+}
+
+named! { modifier_omitted (&str) -> Modifier,
+    do_parse!(
+        complete!(alt!(complete!(tag!("omit")) | complete!(tag!("no")))) >>
+        degree: modifier_degree >>
+        (Modifier::Omitted(degree))
+    )
+}
+
+named! { modifier_number (&str) -> Modifier,
+    map_opt!(modifier_degree, |degree| match degree {
+        5 => Some(Modifier::Power),
+        6 => Some(Modifier::Sixth),
+        7 | 9 | 11 | 13 => Some(Modifier::Extension(degree)),
+        _ => None,
+    })
+}
+
+named! { modifier (&str) -> Modifier,
+    alt!(
+        map!(complete!(tag!("6/9")), |_| Modifier::SixNine) |
+        map!(complete!(tag!("69")), |_| Modifier::SixNine) |
+        map!(complete!(tag!("maj")), |_| Modifier::Major) |
+        map!(complete!(tag!("Maj")), |_| Modifier::Major) |
+        map!(complete!(tag!("M")), |_| Modifier::Major) |
+        map!(complete!(one_of!("Δ△")), |_| Modifier::Delta) |
+        map!(complete!(tag!("min")), |_| Modifier::Minor) |
+        map!(complete!(tag!("mi")), |_| Modifier::Minor) |
+        map!(complete!(one_of!("m-")), |_| Modifier::Minor) |
+        modifier_omitted |
+        map!(complete!(tag!("dim")), |_| Modifier::Diminished) |
+        map!(complete!(one_of!("°o")), |_| Modifier::Diminished) |
+        map!(complete!(one_of!("øØ")), |_| Modifier::HalfDiminished) |
+        map!(complete!(tag!("aug")), |_| Modifier::Augmented) |
+        map!(complete!(char!('+')), |_| Modifier::Augmented) |
+        map!(complete!(tag!("alt")), |_| Modifier::Alt) |
+        map!(complete!(tag!("sus2")), |_| Modifier::Suspended(degrees::II)) |
+        map!(complete!(tag!("sus4")), |_| Modifier::Suspended(degrees::IV)) |
+        map!(complete!(tag!("sus")), |_| Modifier::Suspended(degrees::IV)) |
+        modifier_added |
+        complete!(modifier_altered) |
+        complete!(modifier_number) |
+        map!(complete!(one_of!("(), ")), |_| Modifier::Separator)
+    )
+}
+
+named! { modifiers (&str) -> Vec<Modifier>,
+    many0!(complete!(modifier))
+}
+
+/// Builds the quality of a chord from its written form, such as "m7b5",
+/// "7sus4b9", "maj9#11" or "6/9". The parts are applied to a major triad
+/// from left to right. Returns `None` if any part cannot be understood.
+pub fn chord_quality(input: &str) -> Option<Quality> {
+    use degrees::*;
+    use intervals::*;
+    let modifiers = match modifiers(input) {
+        Ok(("", modifiers)) => modifiers,
+        _ => return None,
+    };
+
+    let mut quality: Quality = vec![Maj3rd, Per5th];
+    // Replaces every note of the same degree as `note`.
+    let set = |quality: &mut Quality, note: Interval| {
+        quality.retain(|i| i.number != note.number);
+        quality.push(note);
+    };
+    let mut seventh = Min7th;
+    let mut delta = false;
+
+    for modifier in modifiers {
+        match modifier {
+            Modifier::Minor => set(&mut quality, Min3rd),
+            Modifier::Major => seventh = Maj7th,
+            Modifier::Delta => {
+                seventh = Maj7th;
+                delta = true;
+            }
+            Modifier::Diminished => {
+                set(&mut quality, Min3rd);
+                set(&mut quality, Dim5th);
+                seventh = Dim7th;
+            }
+            Modifier::HalfDiminished => {
+                set(&mut quality, Min3rd);
+                set(&mut quality, Dim5th);
+                set(&mut quality, Min7th);
+            }
+            Modifier::Augmented => set(&mut quality, Aug5th),
+            Modifier::Power => quality.retain(|i| i.number != III),
+            Modifier::Sixth => set(&mut quality, Maj6th),
+            Modifier::SixNine => {
+                set(&mut quality, Maj6th);
+                set(&mut quality, Maj9th);
+            }
+            Modifier::Extension(degree) => {
+                set(&mut quality, seventh);
+                if degree >= IX {
+                    set(&mut quality, Maj9th);
+                }
+                // The eleventh clashes with a major third, so it is only
+                // implied by 13 in minor chords.
+                let minor = quality.contains(&Min3rd);
+                if degree == XI || (degree == XIII && minor) {
+                    set(&mut quality, Per11th);
+                }
+                if degree == XIII {
+                    set(&mut quality, Maj13th);
+                }
+            }
+            Modifier::Suspended(degree) => {
+                quality.retain(|i| i.number != III);
+                set(&mut quality, natural_interval(degree)?);
+            }
+            Modifier::Added(accidental, degree) => {
+                quality.push(natural_interval(degree)?.altered(accidental));
+            }
+            Modifier::Altered(accidental, degree) => {
+                let natural = natural_interval(degree)?;
+                quality.retain(|note| *note != natural);
+                quality.push(natural.altered(accidental));
+            }
+            Modifier::Omitted(degree) => quality.retain(|i| i.number != degree),
+            Modifier::Alt => {
+                quality.retain(|i| i.number != V);
+                set(&mut quality, seventh);
+                quality.extend(&[Min9th, Aug9th, Aug11th, Min13th]);
+            }
+            Modifier::Separator => (),
+        }
+    }
+
+    if delta && !quality.iter().any(|i| i.number == VII) {
+        quality.push(Maj7th);
+    }
+    quality.sort_by_key(|i| (i.semitones(), i.number));
+    quality.dedup();
+    Some(quality)
+}
+
 // Parser for the empty string.
 named! { command_null (&str) -> Command,
     map!(eof!(), |_| Command::EmptyString)
@@ -107,6 +480,15 @@ named! {command_flats (&str) -> Command,
     )
 }
 
+// Parser for the commands which pause, resume or stop whatever is playing.
+named! {command_transport (&str) -> Command,
+    alt!(
+        map!(complete!(tag!("pause")), |_| Command::Pause) |
+        map!(complete!(tag!("resume")), |_| Command::Resume) |
+        map!(complete!(tag!("stop")), |_| Command::Stop)
+    )
+}
+
 named! { parse_signed_i32 (&str) -> i32,
     map_res!(
         recognize!(tuple!(opt!(char!('-')), nom::character::complete::digit1)),
@@ -119,9 +501,540 @@ named! { command_transpose (&str) -> Command,
         multispace0 >>
         distance: parse_signed_i32 >>
         multispace0 >>
+        chord: chord_symbol >>
+        (Command::Transpose(distance, chord))
+    )
+}
+
+/// Parses bars of chords separated by bar lines, as on a lead sheet. Each
+/// bar holds chords or dots separated by spaces. The closing bar line may be
+/// left out.
+fn bars(input: &str) -> Option<Vec<Vec<Option<ChordSymbol>>>> {
+    let input = input.trim().strip_prefix('|')?;
+    let input = input.strip_suffix('|').unwrap_or(input);
+    input
+        .split('|')
+        .map(|bar| {
+            bar.split_whitespace()
+                .map(|word| match (word, chord_symbol(word)) {
+                    (".", _) => Some(None),
+                    (_, Ok(("", symbol))) => Some(Some(symbol)),
+                    _ => None,
+                })
+                .collect::<Option<Vec<Option<ChordSymbol>>>>()
+                .filter(|slots| !slots.is_empty())
+        })
+        .collect()
+}
+
+named! { command_progression (&str) -> Command,
+    map!(map_opt!(not_line_ending, bars), Command::Progression)
+}
+
+named! { command_transpose_progression (&str) -> Command,
+    do_parse!(
+        alt!(tag!("transpose") | tag!("t")) >>
+        multispace0 >>
+        distance: parse_signed_i32 >>
+        multispace0 >>
+        bars: map_opt!(not_line_ending, bars) >>
+        (Command::TransposeProgression(distance, bars))
+    )
+}
+
+named! { command_keys (&str) -> Command,
+    do_parse!(
+        complete!(tag!("keys")) >>
+        multispace1 >>
+        bars: alt!(
+            map_opt!(not_line_ending, bars) |
+            map_opt!(not_line_ending, |input| chord_list(input)
+                .map(|chords| chords.into_iter().map(|c| vec![Some(c)]).collect()))
+        ) >>
+        (Command::Keys(bars))
+    )
+}
+
+named! { parse_u32 (&str) -> u32,
+    map_res!(nom::character::complete::digit1, u32::from_str)
+}
+
+named! { command_time (&str) -> Command,
+    do_parse!(
+        complete!(tag!("time")) >>
+        multispace1 >>
+        time: map_opt!(
+            separated_pair!(parse_u32, char!('/'), parse_u32),
+            |(beats, unit): (u32, u32)| if beats > 0 && unit.is_power_of_two() {
+                Some(TimeSignature { beats, unit })
+            } else {
+                None
+            }
+        ) >>
+        multispace0 >>
+        (Command::Time(time))
+    )
+}
+
+named! { command_tempo (&str) -> Command,
+    do_parse!(
+        complete!(tag!("tempo")) >>
+        multispace1 >>
+        tempo: verify!(parse_u32, |t: &u32| *t > 0) >>
+        multispace0 >>
+        (Command::Tempo(tempo))
+    )
+}
+
+named! { parse_usize (&str) -> usize,
+    map_res!(nom::character::complete::digit1, usize::from_str)
+}
+
+named! { command_inversion (&str) -> Command,
+    do_parse!(
+        complete!(alt!(complete!(tag!("inversion")) | complete!(tag!("inv")))) >>
+        multispace1 >>
+        inversion: parse_usize >>
+        multispace1 >>
+        chord: chord_symbol >>
+        (Command::Inversion(inversion, chord))
+    )
+}
+
+named! { pub command_scale (&str) -> Command,
+    do_parse!(
+        complete!(tag!("scale")) >>
+        multispace1 >>
         letter: letter >>
-        chord: not_line_ending >>
-        (Command::Transpose(distance, letter, chord.trim().to_string()))
+        name: not_line_ending >>
+        (Command::Scale(letter, name.trim().to_string()))
+    )
+}
+
+/// Splits the size of the chords (3, 7 or 9) off the end of a scale name.
+/// Without a size, triads are built. Without a name, the major scale is used.
+fn scale_and_chord_size(input: &str) -> (String, ChordSize) {
+    let input = input.trim();
+    let (name, size) = match input.rsplit_once(' ').unwrap_or(("", input)) {
+        (name, "3") => (name, ChordSize::Triad),
+        (name, "7") => (name, ChordSize::Seventh),
+        (name, "9") => (name, ChordSize::Ninth),
+        _ => (input, ChordSize::Triad),
+    };
+    match name.trim() {
+        "" => ("major".to_owned(), size),
+        name => (name.to_owned(), size),
+    }
+}
+
+named! { command_diatonic (&str) -> Command,
+    do_parse!(
+        complete!(tag!("diatonic")) >>
+        multispace1 >>
+        letter: letter >>
+        rest: not_line_ending >>
+        ({
+            let (name, size) = scale_and_chord_size(rest);
+            Command::Diatonic(letter, name, size)
+        })
+    )
+}
+
+named! { command_identify (&str) -> Command,
+    do_parse!(
+        complete!(tag!("what")) >>
+        notes: many1!(complete!(preceded!(multispace1, letter))) >>
+        multispace0 >>
+        (Command::Identify(notes))
+    )
+}
+
+named! { degree_accidental (&str) -> Accidental,
+    map!(
+        many0!(complete!(alt!(
+            map!(char!('b'), |_| -1) |
+            map!(char!('#'), |_| 1)
+        ))),
+        |accidentals: Vec<Accidental>| accidentals.iter().sum()
+    )
+}
+
+// Parses a roman numeral, returning its degree and whether it was written
+// in lower case.
+named! { roman_numeral (&str) -> (Degree, bool),
+    alt!(
+        map!(complete!(tag!("VII")), |_| (7, false)) |
+        map!(complete!(tag!("VI")), |_| (6, false)) |
+        map!(complete!(tag!("V")), |_| (5, false)) |
+        map!(complete!(tag!("IV")), |_| (4, false)) |
+        map!(complete!(tag!("III")), |_| (3, false)) |
+        map!(complete!(tag!("II")), |_| (2, false)) |
+        map!(complete!(tag!("I")), |_| (1, false)) |
+        map!(complete!(tag!("vii")), |_| (7, true)) |
+        map!(complete!(tag!("vi")), |_| (6, true)) |
+        map!(complete!(tag!("v")), |_| (5, true)) |
+        map!(complete!(tag!("iv")), |_| (4, true)) |
+        map!(complete!(tag!("iii")), |_| (3, true)) |
+        map!(complete!(tag!("ii")), |_| (2, true)) |
+        map!(complete!(tag!("i")), |_| (1, true))
+    )
+}
+
+/// Turns what follows a roman numeral into the name of a quality, along with
+/// the inversion given by its figures. Lower case numerals are minor unless
+/// the quality says otherwise, and figures such as 65 imply a seventh.
+fn roman_quality(suffix: &str, lower_case: bool) -> (String, usize) {
+    let figures = [
+        ("64", "", 2),
+        ("65", "7", 1),
+        ("43", "7", 2),
+        ("42", "7", 3),
+        ("6", "", 1),
+    ];
+    let (quality, inversion) = figures
+        .iter()
+        .find_map(|(figure, seventh, inversion)| {
+            suffix
+                .strip_suffix(figure)
+                .filter(|rest| !rest.ends_with(|c: char| c.is_ascii_digit()))
+                .map(|rest| (format!("{}{}", rest, seventh), *inversion))
+        })
+        .unwrap_or_else(|| (suffix.to_owned(), 0));
+
+    let diminished = ["°", "o", "ø", "dim", "+", "aug"];
+    if lower_case && !diminished.iter().any(|d| quality.starts_with(d)) {
+        (format!("m{}", quality), inversion)
+    } else {
+        (quality, inversion)
+    }
+}
+
+/// Takes everything up to the next slash, or to the end of the input.
+fn until_slash(input: &str) -> IResult<&str, &str> {
+    nom::bytes::complete::take_till(|c| c == '/')(input)
+}
+
+named! { roman_symbol (&str) -> DegreeSymbol,
+    do_parse!(
+        accidental: degree_accidental >>
+        numeral: roman_numeral >>
+        suffix: until_slash >>
+        of: opt!(complete!(preceded!(
+            char!('/'),
+            pair!(degree_accidental, roman_numeral)
+        ))) >>
+        ({
+            let (degree, lower_case) = numeral;
+            let (quality, inversion) = roman_quality(suffix, lower_case);
+            DegreeSymbol {
+                root: (accidental, degree),
+                quality,
+                inversion,
+                bass: None,
+                of: of.map(|(accidental, (degree, _))| (accidental, degree)),
+            }
+        })
+    )
+}
+
+named! { nashville_degree (&str) -> (Accidental, Degree),
+    pair!(
+        degree_accidental,
+        map_res!(one_of!("1234567"), |c: char| c.to_string().parse::<Degree>())
+    )
+}
+
+named! { nashville_symbol (&str) -> DegreeSymbol,
+    do_parse!(
+        root: nashville_degree >>
+        quality: until_slash >>
+        bass: opt!(complete!(preceded!(char!('/'), nashville_degree))) >>
+        (DegreeSymbol {
+            root,
+            quality: quality.to_owned(),
+            inversion: 0,
+            bass,
+            of: None,
+        })
+    )
+}
+
+named! { pub degree_symbol (&str) -> DegreeSymbol,
+    alt!(roman_symbol | nashville_symbol)
+}
+
+/// Parses a list of roman numerals or Nashville numbers separated by spaces.
+/// Every word must be a whole chord, with a quality that can be built from
+/// its symbol, so that words such as "voicing" are not mistaken for chords.
+fn degree_list(input: &str) -> Option<Vec<DegreeSymbol>> {
+    input
+        .split_whitespace()
+        .map(|word| match degree_symbol(word) {
+            Ok(("", symbol)) if chord_quality(&symbol.quality).is_some() => Some(symbol),
+            _ => None,
+        })
+        .collect::<Option<Vec<DegreeSymbol>>>()
+        .filter(|chords| !chords.is_empty())
+}
+
+named! { command_degrees (&str) -> Command,
+    map!(map_opt!(not_line_ending, degree_list), Command::Degrees)
+}
+
+named! { command_key (&str) -> Command,
+    do_parse!(
+        complete!(tag!("key")) >>
+        multispace1 >>
+        key: key >>
+        multispace0 >>
+        (Command::Key(key))
+    )
+}
+
+/// Parses a list of chords separated by spaces. Every word must be a whole
+/// chord symbol.
+fn chord_list(input: &str) -> Option<Vec<ChordSymbol>> {
+    input
+        .split_whitespace()
+        .map(|word| match chord_symbol(word) {
+            Ok(("", symbol)) => Some(symbol),
+            _ => None,
+        })
+        .collect::<Option<Vec<ChordSymbol>>>()
+        .filter(|chords| !chords.is_empty())
+}
+
+named! { command_lead (&str) -> Command,
+    do_parse!(
+        complete!(tag!("lead")) >>
+        multispace1 >>
+        chords: map_opt!(not_line_ending, chord_list) >>
+        (Command::Lead(chords))
+    )
+}
+
+named! { pub key (&str) -> Key,
+    do_parse!(
+        tonic: letter >>
+        minor: alt!(
+            map!(complete!(tag!(" minor")), |_| true) |
+            map!(complete!(tag!(" major")), |_| false) |
+            map!(complete!(tag!("m")), |_| true) |
+            map!(multispace0, |_| false)
+        ) >>
+        (if minor { Key::minor(tonic) } else { Key::major(tonic) })
+    )
+}
+
+// Parses "in", followed by a key, a colon and a list of chords.
+named! { in_key (&str) -> (Key, Vec<ChordSymbol>),
+    do_parse!(
+        complete!(tag!("in")) >>
+        multispace1 >>
+        key: key >>
+        multispace0 >>
+        complete!(char!(':')) >>
+        chords: map_opt!(not_line_ending, chord_list) >>
+        ((key, chords))
+    )
+}
+
+named! { command_analyze (&str) -> Command,
+    do_parse!(
+        complete!(alt!(complete!(tag!("analyze")) | complete!(tag!("analyse")))) >>
+        multispace1 >>
+        in_key: in_key >>
+        (Command::Analyze(in_key.0, in_key.1))
+    )
+}
+
+named! { command_functions (&str) -> Command,
+    do_parse!(
+        complete!(alt!(complete!(tag!("functions")) | complete!(tag!("cadences")))) >>
+        multispace1 >>
+        in_key: in_key >>
+        (Command::Functions(in_key.0, in_key.1))
+    )
+}
+
+named! { axis (&str) -> Axis,
+    alt!(
+        do_parse!(
+            complete!(tag!("in")) >>
+            multispace1 >>
+            key: key >>
+            (Axis::of_key(&key))
+        ) |
+        do_parse!(
+            complete!(tag!("around")) >>
+            multispace1 >>
+            a: letter >>
+            multispace1 >>
+            b: letter >>
+            (Axis::between(SpelledNote::from_letter(a), SpelledNote::from_letter(b)))
+        )
+    )
+}
+
+named! { command_negative (&str) -> Command,
+    do_parse!(
+        complete!(tag!("negative")) >>
+        multispace1 >>
+        axis: axis >>
+        multispace0 >>
+        complete!(char!(':')) >>
+        chords: map_opt!(not_line_ending, chord_list) >>
+        (Command::Negative(axis, chords))
+    )
+}
+
+/// Splits "G7 in C" into the chord and the key.
+fn chord_in_key(input: &str) -> Option<(ChordSymbol, Key)> {
+    let (chord, in_key) = input.trim().rsplit_once(" in ")?;
+    match (chord_symbol(chord.trim()), key(in_key.trim())) {
+        (Ok(("", chord)), Ok(("", key))) => Some((chord, key)),
+        _ => None,
+    }
+}
+
+named! { command_substitutions (&str) -> Command,
+    do_parse!(
+        complete!(tag!("subs")) >>
+        multispace1 >>
+        chord_in_key: map_opt!(not_line_ending, chord_in_key) >>
+        (Command::Substitutions(chord_in_key.0, chord_in_key.1))
+    )
+}
+
+named! { command_audition (&str) -> Command,
+    do_parse!(
+        complete!(tag!("audition")) >>
+        multispace1 >>
+        number: parse_usize >>
+        multispace0 >>
+        (Command::Audition(number))
+    )
+}
+
+/// Parses a single word as a whole chord symbol.
+fn chord_word(input: &str) -> Option<ChordSymbol> {
+    match chord_symbol(input) {
+        Ok(("", symbol)) => Some(symbol),
+        _ => None,
+    }
+}
+
+/// Splits "C LPR" into the chord and the transforms, or "C to Ab" into the
+/// two chords.
+fn neo_riemannian(input: &str) -> Option<Command> {
+    match input.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [chord, "to", target] => Some(Command::NeoRiemannianPath(
+            chord_word(chord)?,
+            chord_word(target)?,
+        )),
+        [chord, transforms] => Some(Command::NeoRiemannian(
+            chord_word(chord)?,
+            Transform::parse_compound(transforms)?,
+        )),
+        _ => None,
+    }
+}
+
+named! { command_neo_riemannian (&str) -> Command,
+    do_parse!(
+        complete!(tag!("nr")) >>
+        multispace1 >>
+        command: map_opt!(not_line_ending, neo_riemannian) >>
+        (command)
+    )
+}
+
+/// Takes everything up to the next whitespace, or to the end of the input.
+fn file_name(input: &str) -> IResult<&str, &str> {
+    nom::bytes::complete::is_not(" \t\r\n")(input)
+}
+
+named! { command_render (&str) -> Command,
+    do_parse!(
+        complete!(tag!("render")) >>
+        multispace1 >>
+        path: file_name >>
+        command: opt!(complete!(preceded!(multispace1, parse_command))) >>
+        (Command::Render(
+            path.to_owned(),
+            Box::new(command.unwrap_or(Command::EmptyString))
+        ))
+    )
+}
+
+named! { letter_octave (&str) -> LetterOctave,
+    do_parse!(
+        letter: letter >>
+        octave: parse_signed_i32 >>
+        (LetterOctave(letter, octave))
+    )
+}
+
+named! { voicing_rootless (&str) -> Voicing,
+    do_parse!(
+        complete!(tag!("rootless")) >>
+        multispace0 >>
+        voicing: alt!(
+            map!(one_of!("aA"), |_| Voicing::RootlessA) |
+            map!(one_of!("bB"), |_| Voicing::RootlessB)
+        ) >>
+        (voicing)
+    )
+}
+
+named! { voicing_spread (&str) -> Voicing,
+    map_opt!(
+        do_parse!(
+            complete!(tag!("spread")) >>
+            range: opt!(complete!(do_parse!(
+                multispace1 >>
+                low: letter_octave >>
+                multispace1 >>
+                high: letter_octave >>
+                ((low, high))
+            ))) >>
+            (range.unwrap_or((SPREAD_LOW, SPREAD_HIGH)))
+        ),
+        |(low, high)| Voicing::spread(low, high)
+    )
+}
+
+named! { pub voicing (&str) -> Voicing,
+    alt!(
+        map!(complete!(tag!("close")), |_| Voicing::Close) |
+        map!(complete!(tag!("open")), |_| Voicing::Open) |
+        map!(complete!(tag!("drop2and4")), |_| Voicing::Drop24) |
+        map!(complete!(tag!("drop24")), |_| Voicing::Drop24) |
+        map!(complete!(tag!("drop2")), |_| Voicing::Drop2) |
+        map!(complete!(tag!("drop3")), |_| Voicing::Drop3) |
+        map!(complete!(tag!("shell")), |_| Voicing::Shell) |
+        voicing_rootless |
+        voicing_spread
+    )
+}
+
+named! { command_voicing (&str) -> Command,
+    do_parse!(
+        complete!(tag!("voicing")) >>
+        multispace1 >>
+        voicing: voicing >>
+        multispace0 >>
+        (Command::Voicing(voicing))
+    )
+}
+
+named! { command_symbols (&str) -> Command,
+    do_parse!(
+        complete!(tag!("symbols")) >>
+        multispace1 >>
+        symbols: map_res!(nom::character::complete::alpha1, ChordSymbols::from_str) >>
+        multispace0 >>
+        (Command::Symbols(symbols))
     )
 }
 
@@ -131,14 +1044,37 @@ named! { pub parse_command (&str) -> Command,
         command_null |
         command_flats |
         command_sharps |
+        command_transport |
         command_transpose |
-        command_chord
+        command_transpose_progression |
+        command_inversion |
+        command_scale |
+        command_diatonic |
+        command_identify |
+        command_voicing |
+        command_lead |
+        command_symbols |
+        command_analyze |
+        command_functions |
+        command_substitutions |
+        command_audition |
+        command_neo_riemannian |
+        command_negative |
+        command_render |
+        command_key |
+        command_time |
+        command_tempo |
+        command_keys |
+        command_progression |
+        command_chord |
+        command_degrees
     )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::database::initialise_database;
     use nom::Err::*;
     use nom::Needed::*;
     use std::num::*;
@@ -188,21 +1124,524 @@ mod tests {
         assert_eq!(parse_command("flat"), Ok(("", Command::Flats)));
     }
 
+    #[test]
+    fn command_transport() {
+        assert_eq!(parse_command("pause"), Ok(("", Command::Pause)));
+        assert_eq!(parse_command("resume"), Ok(("", Command::Resume)));
+        assert_eq!(parse_command("stop"), Ok(("", Command::Stop)));
+    }
+
+    fn symbol(root: Letter, quality: &str, bass: Option<Letter>) -> ChordSymbol {
+        ChordSymbol {
+            root,
+            quality: quality.to_owned(),
+            bass,
+        }
+    }
+
+    #[test]
+    fn command_chord() {
+        assert_eq!(
+            parse_command("Cmaj7"),
+            Ok(("", Command::Chord(symbol(C, "maj7", None))))
+        );
+        assert_eq!(
+            parse_command("C/E"),
+            Ok(("", Command::Chord(symbol(C, "", Some(E)))))
+        );
+        assert_eq!(
+            parse_command("Am7/G"),
+            Ok(("", Command::Chord(symbol(A, "m7", Some(G)))))
+        );
+        assert_eq!(
+            parse_command("G7 / F"),
+            Ok(("", Command::Chord(symbol(G, "7", Some(F)))))
+        );
+        assert_eq!(
+            parse_command("Bb/Ab"),
+            Ok(("", Command::Chord(symbol(Bb, "", Some(Ab)))))
+        );
+        // Not a bass note, so it stays part of the quality.
+        assert_eq!(
+            parse_command("C6/9"),
+            Ok(("", Command::Chord(symbol(C, "6/9", None))))
+        );
+    }
+
     #[test]
     fn command_transpose() {
         assert_eq!(
             parse_command("transpose 5 C#maj7"),
-            Ok(("", Command::Transpose(5, Csh, "maj7".to_owned())))
+            Ok(("", Command::Transpose(5, symbol(Csh, "maj7", None))))
         );
 
         assert_eq!(
             parse_command("transpose -7 C#maj7"),
-            Ok(("", Command::Transpose(-7, Csh, "maj7".to_owned())))
+            Ok(("", Command::Transpose(-7, symbol(Csh, "maj7", None))))
         );
 
         assert_eq!(
             parse_command("t -7 C#maj7"),
-            Ok(("", Command::Transpose(-7, Csh, "maj7".to_owned())))
+            Ok(("", Command::Transpose(-7, symbol(Csh, "maj7", None))))
+        );
+
+        assert_eq!(
+            parse_command("t -7 C#maj7/G#"),
+            Ok(("", Command::Transpose(-7, symbol(Csh, "maj7", Some(Gsh)))))
+        );
+    }
+
+    #[test]
+    fn command_inversion() {
+        assert_eq!(
+            parse_command("inv 2 Cmaj7"),
+            Ok(("", Command::Inversion(2, symbol(C, "maj7", None))))
+        );
+        assert_eq!(
+            parse_command("inversion 1 Ebm"),
+            Ok(("", Command::Inversion(1, symbol(Eb, "m", None))))
+        );
+        assert!(parse_command("inv Cmaj7").is_err());
+    }
+
+    #[test]
+    fn command_scale() {
+        assert_eq!(
+            parse_command("scale D dorian"),
+            Ok(("", Command::Scale(D, "dorian".to_owned())))
+        );
+
+        assert_eq!(
+            parse_command("scale Bb harmonic minor"),
+            Ok(("", Command::Scale(Bb, "harmonic minor".to_owned())))
+        );
+
+        assert!(parse_command("scaleD dorian").is_err());
+    }
+
+    #[test]
+    fn command_identify() {
+        assert_eq!(
+            parse_command("what E G Bb D"),
+            Ok(("", Command::Identify(vec![E, G, Bb, D])))
+        );
+        assert_eq!(
+            parse_command("what C# E "),
+            Ok(("", Command::Identify(vec![Csh, E])))
+        );
+        assert!(parse_command("what").is_err());
+    }
+
+    #[test]
+    fn command_diatonic() {
+        assert_eq!(
+            parse_command("diatonic Eb major 7"),
+            Ok((
+                "",
+                Command::Diatonic(Eb, "major".to_owned(), ChordSize::Seventh)
+            ))
+        );
+
+        assert_eq!(
+            parse_command("diatonic A harmonic minor"),
+            Ok((
+                "",
+                Command::Diatonic(A, "harmonic minor".to_owned(), ChordSize::Triad)
+            ))
+        );
+
+        assert_eq!(
+            parse_command("diatonic C 9"),
+            Ok((
+                "",
+                Command::Diatonic(C, "major".to_owned(), ChordSize::Ninth)
+            ))
+        );
+
+        assert_eq!(
+            parse_command("diatonic D locrian natural 6"),
+            Ok((
+                "",
+                Command::Diatonic(D, "locrian natural 6".to_owned(), ChordSize::Triad)
+            ))
+        );
+    }
+
+    #[test]
+    fn command_voicing() {
+        assert_eq!(
+            parse_command("voicing drop2"),
+            Ok(("", Command::Voicing(Voicing::Drop2)))
+        );
+        assert_eq!(
+            parse_command("voicing drop2and4"),
+            Ok(("", Command::Voicing(Voicing::Drop24)))
+        );
+        assert_eq!(
+            parse_command("voicing rootless B"),
+            Ok(("", Command::Voicing(Voicing::RootlessB)))
+        );
+        assert_eq!(
+            parse_command("voicing spread"),
+            Ok((
+                "",
+                Command::Voicing(Voicing::Spread(SpreadRange::default()))
+            ))
+        );
+        assert_eq!(
+            parse_command("voicing spread Bb1 G5"),
+            Ok((
+                "",
+                Command::Voicing(Voicing::spread(LetterOctave(Bb, 1), LetterOctave(G, 5)).unwrap())
+            ))
+        );
+        assert!(parse_command("voicing spread C6 C2").is_err());
+        assert!(parse_command("voicing spread C4 F4").is_err());
+        assert!(parse_command("voicing").is_err());
+    }
+
+    #[test]
+    fn command_symbols() {
+        assert_eq!(
+            parse_command("symbols jazz"),
+            Ok(("", Command::Symbols(ChordSymbols::Jazz)))
+        );
+        assert!(parse_command("symbols rock").is_err());
+    }
+
+    #[test]
+    fn command_lead() {
+        assert_eq!(
+            parse_command("lead Dm7 G7/F Cmaj7"),
+            Ok((
+                "",
+                Command::Lead(vec![
+                    symbol(D, "m7", None),
+                    symbol(G, "7", Some(F)),
+                    symbol(C, "maj7", None)
+                ])
+            ))
+        );
+        assert!(parse_command("lead").is_err());
+        assert!(parse_command("lead C x").is_err());
+    }
+
+    #[test]
+    fn command_analyze() {
+        assert_eq!(
+            parse_command("analyze in C: Dm7 G7 Cmaj7 A7"),
+            Ok((
+                "",
+                Command::Analyze(
+                    Key::major(C),
+                    vec![
+                        symbol(D, "m7", None),
+                        symbol(G, "7", None),
+                        symbol(C, "maj7", None),
+                        symbol(A, "7", None)
+                    ]
+                )
+            ))
+        );
+        assert_eq!(
+            parse_command("analyse in F# minor: C#7/F"),
+            Ok((
+                "",
+                Command::Analyze(Key::minor(Fsh), vec![symbol(Csh, "7", Some(F))])
+            ))
+        );
+        assert_eq!(
+            parse_command("cadences in Am: Dm/F E"),
+            Ok((
+                "",
+                Command::Functions(
+                    Key::minor(A),
+                    vec![symbol(D, "m", Some(F)), symbol(E, "", None)]
+                )
+            ))
+        );
+        assert_eq!(
+            parse_command("subs G7/B in C"),
+            Ok((
+                "",
+                Command::Substitutions(symbol(G, "7", Some(B)), Key::major(C))
+            ))
+        );
+        assert_eq!(parse_command("audition 2"), Ok(("", Command::Audition(2))));
+        assert!(parse_command("subs G7").is_err());
+        assert_eq!(
+            parse_command("nr C LPR"),
+            Ok((
+                "",
+                Command::NeoRiemannian(
+                    symbol(C, "", None),
+                    vec![Transform::L, Transform::P, Transform::R]
+                )
+            ))
+        );
+        assert_eq!(
+            parse_command("nr Cm to Ab"),
+            Ok((
+                "",
+                Command::NeoRiemannianPath(symbol(C, "m", None), symbol(Ab, "", None))
+            ))
+        );
+        assert!(parse_command("nr C LPX").is_err());
+        assert_eq!(
+            parse_command("negative in C: G7"),
+            Ok((
+                "",
+                Command::Negative(Axis::of_key(&Key::major(C)), vec![symbol(G, "7", None)])
+            ))
+        );
+        assert_eq!(
+            parse_command("negative around E Eb: G7"),
+            parse_command("negative in C: G7")
+        );
+        assert_eq!(
+            parse_command("render out.wav Cmaj7"),
+            Ok((
+                "",
+                Command::Render(
+                    "out.wav".to_owned(),
+                    Box::new(Command::Chord(symbol(C, "maj7", None)))
+                )
+            ))
+        );
+        assert_eq!(
+            parse_command("render out.wav"),
+            Ok((
+                "",
+                Command::Render("out.wav".to_owned(), Box::new(Command::EmptyString))
+            ))
+        );
+        assert_eq!(key("Bbm"), Ok(("", Key::minor(Bb))));
+        assert!(parse_command("analyze in C:").is_err());
+    }
+
+    #[test]
+    fn command_progression() {
+        let c = Some(symbol(C, "", None));
+        assert_eq!(
+            parse_command("| C . Am . | F G7 | C |"),
+            Ok((
+                "",
+                Command::Progression(vec![
+                    vec![c.clone(), None, Some(symbol(A, "m", None)), None],
+                    vec![Some(symbol(F, "", None)), Some(symbol(G, "7", None))],
+                    vec![c.clone()],
+                ])
+            ))
+        );
+        assert_eq!(
+            parse_command("t -2 | C/E | C"),
+            Ok((
+                "",
+                Command::TransposeProgression(
+                    -2,
+                    vec![vec![Some(symbol(C, "", Some(E)))], vec![c]]
+                )
+            ))
         );
+        assert!(parse_command("| C | | F |").is_err());
+
+        assert_eq!(
+            parse_command("time 6/8"),
+            Ok(("", Command::Time(TimeSignature { beats: 6, unit: 8 })))
+        );
+        assert!(parse_command("time 3/5").is_err());
+        assert_eq!(parse_command("tempo 90"), Ok(("", Command::Tempo(90))));
+        assert!(parse_command("tempo 0").is_err());
+
+        assert_eq!(
+            parse_command("keys F G7"),
+            Ok((
+                "",
+                Command::Keys(vec![
+                    vec![Some(symbol(F, "", None))],
+                    vec![Some(symbol(G, "7", None))]
+                ])
+            ))
+        );
+        assert_eq!(
+            parse_command("keys | F . G7 . |"),
+            Ok((
+                "",
+                Command::Keys(vec![vec![
+                    Some(symbol(F, "", None)),
+                    None,
+                    Some(symbol(G, "7", None)),
+                    None
+                ]])
+            ))
+        );
+    }
+
+    #[test]
+    fn command_degrees() {
+        let roman = |degree, quality: &str, of| DegreeSymbol {
+            root: (0, degree),
+            quality: quality.to_owned(),
+            inversion: 0,
+            bass: None,
+            of,
+        };
+        assert_eq!(
+            parse_command("ii7 V7/V bVI"),
+            Ok((
+                "",
+                Command::Degrees(vec![
+                    roman(2, "m7", None),
+                    roman(5, "7", Some((0, 5))),
+                    DegreeSymbol {
+                        root: (-1, 6),
+                        ..roman(6, "", None)
+                    }
+                ])
+            ))
+        );
+        assert_eq!(
+            degree_symbol("viiø65"),
+            Ok((
+                "",
+                DegreeSymbol {
+                    inversion: 1,
+                    ..roman(7, "ø7", None)
+                }
+            ))
+        );
+        assert_eq!(
+            degree_symbol("6-/3"),
+            Ok((
+                "",
+                DegreeSymbol {
+                    bass: Some((0, 3)),
+                    ..roman(6, "-", None)
+                }
+            ))
+        );
+        assert_eq!(
+            parse_command("key Bb"),
+            Ok(("", Command::Key(Key::major(Bb))))
+        );
+        assert!(parse_command("ii7 x").is_err());
+        assert!(parse_command("8").is_err());
+    }
+
+    #[test]
+    fn degrees_in_keys() {
+        let conn = initialise_database().unwrap();
+        crate::chord_library::populate_database(&conn);
+        let chords = |key: Key, input: &str| -> Vec<Chord> {
+            match parse_command(input) {
+                Ok(("", Command::Degrees(symbols))) => symbols
+                    .iter()
+                    .map(|s| s.to_chord(&key, 4, &conn).unwrap())
+                    .collect(),
+                _ => panic!("Could not parse {}", input),
+            }
+        };
+        let chord = |input: &str| match chord_symbol(input) {
+            Ok(("", symbol)) => symbol.to_chord(4, &conn).unwrap(),
+            _ => panic!("Could not parse {}", input),
+        };
+
+        assert_eq!(
+            chords(Key::major(Bb), "ii7 V7 Imaj7 bVI V/V I6"),
+            vec![
+                chord("Cm7"),
+                chord("F7"),
+                chord("Bbmaj7"),
+                chord("Gb"),
+                chord("C"),
+                chord("Bb/D"),
+            ]
+        );
+        assert_eq!(
+            chords(Key::major(G), "1 4 5m 6- 1/3 b7"),
+            vec![
+                chord("G"),
+                chord("C"),
+                chord("Dm"),
+                chord("Em"),
+                chord("G/B"),
+                chord("F"),
+            ]
+        );
+        assert_eq!(
+            chords(Key::minor(A), "i iv V7 vii°7 V7/III"),
+            vec![
+                chord("Am"),
+                chord("Dm"),
+                chord("E7"),
+                chord("G#dim7"),
+                chord("G7"),
+            ]
+        );
+    }
+
+    #[test]
+    fn chord_qualities() {
+        use intervals::*;
+        let quality = |input| chord_quality(input).unwrap();
+
+        assert_eq!(quality(""), vec![Maj3rd, Per5th]);
+        assert_eq!(quality("m7b5"), vec![Min3rd, Dim5th, Min7th]);
+        assert_eq!(quality("ø7"), quality("m7b5"));
+        assert_eq!(
+            quality("7b9#11"),
+            vec![Maj3rd, Per5th, Min7th, Min9th, Aug11th]
+        );
+        assert_eq!(
+            quality("m11"),
+            vec![Min3rd, Per5th, Min7th, Maj9th, Per11th]
+        );
+        assert_eq!(
+            quality("maj9#11"),
+            vec![Maj3rd, Per5th, Maj7th, Maj9th, Aug11th]
+        );
+        assert_eq!(quality("13"), vec![Maj3rd, Per5th, Min7th, Maj9th, Maj13th]);
+        assert_eq!(quality("sus4"), vec![Per4th, Per5th]);
+        assert_eq!(quality("sus2"), vec![Maj2nd, Per5th]);
+        assert_eq!(quality("add9"), vec![Maj3rd, Per5th, Maj9th]);
+        assert_eq!(quality("7sus4b9"), vec![Per4th, Per5th, Min7th, Min9th]);
+        assert_eq!(quality("6/9"), vec![Maj3rd, Per5th, Maj6th, Maj9th]);
+        assert_eq!(quality("mMaj7"), vec![Min3rd, Per5th, Maj7th]);
+        assert_eq!(quality("dim7"), vec![Min3rd, Dim5th, Dim7th]);
+        assert_eq!(quality("-Δ"), quality("mMaj7"));
+        assert_eq!(quality("7(b9, b13)"), quality("7b9b13"));
+        assert_eq!(quality("7no5"), vec![Maj3rd, Min7th]);
+        assert_eq!(quality("add9no3"), vec![Per5th, Maj9th]);
+        assert_eq!(quality("7omit5"), quality("7no5"));
+        assert_eq!(quality("madd9"), vec![Min3rd, Per5th, Maj9th]);
+        assert_eq!(quality("5"), vec![Per5th]);
+        assert_eq!(
+            quality("7alt"),
+            vec![Maj3rd, Min7th, Min9th, Aug9th, Aug11th, Min13th]
+        );
+
+        assert_eq!(chord_quality("7x"), None);
+        assert_eq!(chord_quality("add8"), None);
+        assert_eq!(chord_quality("8"), None);
+    }
+
+    #[test]
+    fn extended_chords() {
+        use crate::chord_library;
+        let conn = initialise_database().unwrap();
+        chord_library::populate_database(&conn);
+        let notes = |input| match chord_symbol(input) {
+            Ok(("", symbol)) => symbol.to_chord(4, &conn).unwrap().to_string(),
+            _ => panic!("Could not parse {}", input),
+        };
+
+        assert_eq!(notes("C7b9#11"), "C4 E4 G4 Bb4 Db5 F#5");
+        assert_eq!(notes("Dm11"), "D4 F4 A4 C5 E5 G5");
+        assert_eq!(notes("Fmaj9#11"), "F4 A4 C5 E5 G5 B5");
+        assert_eq!(notes("Gsus4"), "G4 C5 D5");
+        assert_eq!(notes("Cadd9"), "C4 E4 G4 D5");
+        assert_eq!(notes("A7sus4b9"), "A4 D5 E5 G5 Bb5");
+        assert_eq!(notes("C6/9"), "C4 E4 G4 A4 D5");
+        assert_eq!(notes("C6/9/E"), "E4 G4 A4 C5 D5");
     }
 }
@@ -0,0 +1,472 @@
+use crate::music_theory::*;
+use std::fmt;
+
+/// An unordered collection of pitch classes, numbered from 0 (C) to 11 (B),
+/// as used in the analysis of post-tonal music. Octaves and spelling are
+/// ignored: C4, C5 and B#3 are all pitch class 0.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct PitchClassSet {
+    /// The pitch classes, in ascending order and without repetitions.
+    pitch_classes: Vec<Semitones>,
+}
+
+/// Prime forms of every set class of up to six pitch classes, in the order
+/// of Forte's list. Set classes of seven to eleven pitch classes take the
+/// number of their complement: 7-35 is the complement of 5-35.
+const FORTE_PRIME_FORMS: [&[&[Semitones]]; 7] = [
+    &[&[]],
+    &[&[0]],
+    &[&[0, 1], &[0, 2], &[0, 3], &[0, 4], &[0, 5], &[0, 6]],
+    &[
+        &[0, 1, 2],
+        &[0, 1, 3],
+        &[0, 1, 4],
+        &[0, 1, 5],
+        &[0, 1, 6],
+        &[0, 2, 4],
+        &[0, 2, 5],
+        &[0, 2, 6],
+        &[0, 2, 7],
+        &[0, 3, 6],
+        &[0, 3, 7],
+        &[0, 4, 8],
+    ],
+    &[
+        &[0, 1, 2, 3],
+        &[0, 1, 2, 4],
+        &[0, 1, 3, 4],
+        &[0, 1, 2, 5],
+        &[0, 1, 2, 6],
+        &[0, 1, 2, 7],
+        &[0, 1, 4, 5],
+        &[0, 1, 5, 6],
+        &[0, 1, 6, 7],
+        &[0, 2, 3, 5],
+        &[0, 1, 3, 5],
+        &[0, 2, 3, 6],
+        &[0, 1, 3, 6],
+        &[0, 2, 3, 7],
+        &[0, 1, 4, 6],
+        &[0, 1, 5, 7],
+        &[0, 3, 4, 7],
+        &[0, 1, 4, 7],
+        &[0, 1, 4, 8],
+        &[0, 1, 5, 8],
+        &[0, 2, 4, 6],
+        &[0, 2, 4, 7],
+        &[0, 2, 5, 7],
+        &[0, 2, 4, 8],
+        &[0, 2, 6, 8],
+        &[0, 3, 5, 8],
+        &[0, 2, 5, 8],
+        &[0, 3, 6, 9],
+        &[0, 1, 3, 7],
+    ],
+    &[
+        &[0, 1, 2, 3, 4],
+        &[0, 1, 2, 3, 5],
+        &[0, 1, 2, 4, 5],
+        &[0, 1, 2, 3, 6],
+        &[0, 1, 2, 3, 7],
+        &[0, 1, 2, 5, 6],
+        &[0, 1, 2, 6, 7],
+        &[0, 2, 3, 4, 6],
+        &[0, 1, 2, 4, 6],
+        &[0, 1, 3, 4, 6],
+        &[0, 2, 3, 4, 7],
+        &[0, 1, 3, 5, 6],
+        &[0, 1, 2, 4, 8],
+        &[0, 1, 2, 5, 7],
+        &[0, 1, 2, 6, 8],
+        &[0, 1, 3, 4, 7],
+        &[0, 1, 3, 4, 8],
+        &[0, 1, 4, 5, 7],
+        &[0, 1, 3, 6, 7],
+        &[0, 1, 5, 6, 8],
+        &[0, 1, 4, 5, 8],
+        &[0, 1, 4, 7, 8],
+        &[0, 2, 3, 5, 7],
+        &[0, 1, 3, 5, 7],
+        &[0, 2, 3, 5, 8],
+        &[0, 2, 4, 5, 8],
+        &[0, 1, 3, 5, 8],
+        &[0, 2, 3, 6, 8],
+        &[0, 1, 3, 6, 8],
+        &[0, 1, 4, 6, 8],
+        &[0, 1, 3, 6, 9],
+        &[0, 1, 4, 6, 9],
+        &[0, 2, 4, 6, 8],
+        &[0, 2, 4, 6, 9],
+        &[0, 2, 4, 7, 9],
+        &[0, 1, 2, 4, 7],
+        &[0, 3, 4, 5, 8],
+        &[0, 1, 2, 5, 8],
+    ],
+    &[
+        &[0, 1, 2, 3, 4, 5],
+        &[0, 1, 2, 3, 4, 6],
+        &[0, 1, 2, 3, 5, 6],
+        &[0, 1, 2, 4, 5, 6],
+        &[0, 1, 2, 3, 6, 7],
+        &[0, 1, 2, 5, 6, 7],
+        &[0, 1, 2, 6, 7, 8],
+        &[0, 2, 3, 4, 5, 7],
+        &[0, 1, 2, 3, 5, 7],
+        &[0, 1, 3, 4, 5, 7],
+        &[0, 1, 2, 4, 5, 7],
+        &[0, 1, 2, 4, 6, 7],
+        &[0, 1, 3, 4, 6, 7],
+        &[0, 1, 3, 4, 5, 8],
+        &[0, 1, 2, 4, 5, 8],
+        &[0, 1, 4, 5, 6, 8],
+        &[0, 1, 2, 4, 7, 8],
+        &[0, 1, 2, 5, 7, 8],
+        &[0, 1, 3, 4, 7, 8],
+        &[0, 1, 4, 5, 8, 9],
+        &[0, 2, 3, 4, 6, 8],
+        &[0, 1, 2, 4, 6, 8],
+        &[0, 2, 3, 5, 6, 8],
+        &[0, 1, 3, 4, 6, 8],
+        &[0, 1, 3, 5, 6, 8],
+        &[0, 1, 3, 5, 7, 8],
+        &[0, 1, 3, 4, 6, 9],
+        &[0, 1, 3, 5, 6, 9],
+        &[0, 1, 3, 6, 8, 9],
+        &[0, 1, 3, 6, 7, 9],
+        &[0, 1, 4, 5, 7, 9],
+        &[0, 2, 4, 5, 7, 9],
+        &[0, 2, 3, 5, 7, 9],
+        &[0, 1, 3, 5, 7, 9],
+        &[0, 2, 4, 6, 8, 10],
+        &[0, 1, 2, 3, 4, 7],
+        &[0, 1, 2, 3, 4, 8],
+        &[0, 1, 2, 3, 7, 8],
+        &[0, 2, 3, 4, 5, 8],
+        &[0, 1, 2, 3, 5, 8],
+        &[0, 1, 2, 3, 6, 8],
+        &[0, 1, 2, 3, 6, 9],
+        &[0, 1, 2, 5, 6, 8],
+        &[0, 1, 2, 5, 6, 9],
+        &[0, 2, 3, 4, 6, 9],
+        &[0, 1, 2, 4, 6, 9],
+        &[0, 1, 2, 4, 7, 9],
+        &[0, 1, 2, 5, 7, 9],
+        &[0, 1, 3, 4, 7, 9],
+        &[0, 1, 4, 6, 7, 9],
+    ],
+];
+
+/// Writes a pitch class as a single character, with T and E standing for 10
+/// and 11.
+pub fn pitch_class_char(pitch_class: Semitones) -> char {
+    match pitch_class {
+        10 => 'T',
+        11 => 'E',
+        pc => std::char::from_digit(pc as u32, 10).unwrap_or('?'),
+    }
+}
+
+/// Returns the distances from the first pitch class up to each of the
+/// others, from the last one backwards. Of two orderings of a set, the one
+/// with the smaller distances is the more tightly packed.
+fn packing(ordering: &[Semitones]) -> Vec<Semitones> {
+    let first = ordering.first().copied().unwrap_or(0);
+    ordering
+        .iter()
+        .rev()
+        .map(|pc| (pc - first).rem_euclid(12))
+        .collect()
+}
+
+impl PitchClassSet {
+    /// Builds a set out of any numbers, which are reduced to pitch classes
+    /// from 0 to 11. Repeated pitch classes are only kept once.
+    pub fn new<I: IntoIterator<Item = Semitones>>(pitch_classes: I) -> PitchClassSet {
+        let mut pitch_classes: Vec<Semitones> = pitch_classes
+            .into_iter()
+            .map(|pc| pc.rem_euclid(12))
+            .collect();
+        pitch_classes.sort_unstable();
+        pitch_classes.dedup();
+        PitchClassSet { pitch_classes }
+    }
+
+    pub fn from_letters(letters: &[Letter]) -> PitchClassSet {
+        PitchClassSet::new(letters.iter().map(|l| pitch_class(*l)))
+    }
+
+    pub fn from_notes(notes: &[LetterOctave]) -> PitchClassSet {
+        PitchClassSet::new(notes.iter().map(|n| pitch_class(n.letter())))
+    }
+
+    /// The pitch classes, in ascending order.
+    pub fn pitch_classes(&self) -> &[Semitones] {
+        &self.pitch_classes
+    }
+
+    /// Number of pitch classes in the set, also known as its cardinality.
+    pub fn len(&self) -> usize {
+        self.pitch_classes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pitch_classes.is_empty()
+    }
+
+    pub fn contains(&self, pitch_class: Semitones) -> bool {
+        self.pitch_classes.contains(&pitch_class.rem_euclid(12))
+    }
+
+    /// Returns the set moved up by `interval` semitones, also known as Tn.
+    pub fn transposed(&self, interval: Semitones) -> PitchClassSet {
+        PitchClassSet::new(self.pitch_classes.iter().map(|pc| pc + interval))
+    }
+
+    /// Returns the set inverted around C, then moved up by `interval`
+    /// semitones, also known as TnI.
+    pub fn inverted(&self, interval: Semitones) -> PitchClassSet {
+        PitchClassSet::new(self.pitch_classes.iter().map(|pc| interval - pc))
+    }
+
+    /// Returns every pitch class not in the set.
+    pub fn complement(&self) -> PitchClassSet {
+        PitchClassSet::new((0..12).filter(|pc| !self.contains(*pc)))
+    }
+
+    /// Returns the pitch classes in their most tightly packed ordering: the
+    /// rotation with the smallest span from first to last, ties being broken
+    /// by the span from first to second-last and so on. If rotations are
+    /// still tied, the one starting on the lowest pitch class is chosen.
+    pub fn normal_form(&self) -> Vec<Semitones> {
+        (0..self.len())
+            .map(|start| {
+                let mut rotation = self.pitch_classes[start..].to_vec();
+                rotation.extend_from_slice(&self.pitch_classes[..start]);
+                rotation
+            })
+            .min_by_key(|rotation| (packing(rotation), rotation.first().copied()))
+            .unwrap_or_default()
+    }
+
+    /// Returns the normal form of the set or of its inversion, whichever is
+    /// more tightly packed, moved to start on 0. Sets which are
+    /// transpositions or inversions of one another share a prime form.
+    pub fn prime_form(&self) -> Vec<Semitones> {
+        let zeroed = |normal: Vec<Semitones>| -> Vec<Semitones> {
+            let first = normal.first().copied().unwrap_or(0);
+            normal
+                .iter()
+                .map(|pc| (pc - first).rem_euclid(12))
+                .collect()
+        };
+        let original = zeroed(self.normal_form());
+        let inversion = zeroed(self.inverted(0).normal_form());
+        if packing(&inversion) < packing(&original) {
+            inversion
+        } else {
+            original
+        }
+    }
+
+    /// Counts the intervals between every pair of pitch classes, by interval
+    /// class: the first entry counts minor seconds and major sevenths, the
+    /// last counts tritones.
+    pub fn interval_vector(&self) -> [usize; 6] {
+        let mut vector = [0; 6];
+        for (i, a) in self.pitch_classes.iter().enumerate() {
+            for b in &self.pitch_classes[i + 1..] {
+                let interval = (b - a).rem_euclid(12);
+                let class = interval.min(12 - interval);
+                vector[class as usize - 1] += 1;
+            }
+        }
+        vector
+    }
+
+    /// Returns the number of the set class in Forte's list, without the Z.
+    fn forte_index(&self) -> Option<usize> {
+        let set = if self.len() > 6 {
+            self.complement()
+        } else {
+            self.clone()
+        };
+        let prime = set.prime_form();
+        FORTE_PRIME_FORMS
+            .get(set.len())?
+            .iter()
+            .position(|p| PitchClassSet::new(p.iter().copied()).prime_form() == prime)
+            .map(|index| index + 1)
+    }
+
+    /// Returns the name of the set class in Forte's list, such as "4-Z15".
+    /// Every set of pitch classes has one, apart from the empty set.
+    pub fn forte_number(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let z = if self.z_partner().is_some() { "Z" } else { "" };
+        Some(format!("{}-{}{}", self.len(), z, self.forte_index()?))
+    }
+
+    /// Returns true if the sets are transpositions or inversions of one
+    /// another, i.e. if they belong to the same set class.
+    pub fn is_equivalent(&self, other: &PitchClassSet) -> bool {
+        self.len() == other.len() && self.prime_form() == other.prime_form()
+    }
+
+    /// Returns the n for which Tn turns this set into `other`, if any. A
+    /// symmetrical set may be reached by several; the smallest is returned.
+    pub fn transposition_to(&self, other: &PitchClassSet) -> Option<Semitones> {
+        (0..12).find(|n| self.transposed(*n) == *other)
+    }
+
+    /// Returns the n for which TnI turns this set into `other`, if any.
+    pub fn inversion_to(&self, other: &PitchClassSet) -> Option<Semitones> {
+        (0..12).find(|n| self.inverted(*n) == *other)
+    }
+
+    /// Returns true if the sets share an interval vector without belonging
+    /// to the same set class, as with 4-Z15 and 4-Z29.
+    pub fn is_z_related(&self, other: &PitchClassSet) -> bool {
+        self.interval_vector() == other.interval_vector() && !self.is_equivalent(other)
+    }
+
+    /// Returns the prime form of the set class which is Z-related to this
+    /// one, if there is one.
+    pub fn z_partner(&self) -> Option<PitchClassSet> {
+        // Only sets of four to eight pitch classes can be Z-related, and the
+        // partner of a set larger than six is the complement of the partner
+        // of its complement.
+        let (set, complemented) = if self.len() > 6 {
+            (self.complement(), true)
+        } else {
+            (self.clone(), false)
+        };
+        let partner = FORTE_PRIME_FORMS
+            .get(set.len())?
+            .iter()
+            .map(|p| PitchClassSet::new(p.iter().copied()))
+            .find(|p| set.is_z_related(p))?;
+        if complemented {
+            Some(PitchClassSet::new(partner.complement().prime_form()))
+        } else {
+            Some(partner)
+        }
+    }
+}
+
+impl fmt::Display for PitchClassSet {
+    /// Writes the set in braces, e.g. "{0,4,7}".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pitch_classes: Vec<String> = self
+            .pitch_classes
+            .iter()
+            .map(|pc| pitch_class_char(*pc).to_string())
+            .collect();
+        write!(f, "{{{}}}", pitch_classes.join(","))
+    }
+}
+
+impl Chord {
+    /// Returns the pitch classes of the notes of the chord.
+    pub fn pitch_class_set(&self) -> PitchClassSet {
+        PitchClassSet::from_notes(&self.notes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intervals::*;
+    use Letter::*;
+
+    fn set(pitch_classes: &[Semitones]) -> PitchClassSet {
+        PitchClassSet::new(pitch_classes.iter().copied())
+    }
+
+    #[test]
+    fn forms() {
+        let c_major = Chord {
+            root: LetterOctave(C, 4),
+            quality: vec![Maj3rd, Per5th],
+            bass: Some(E),
+        };
+        let triad = c_major.pitch_class_set();
+        assert_eq!(triad, set(&[0, 4, 7]));
+        assert_eq!(triad.to_string(), "{0,4,7}");
+        assert_eq!(triad.normal_form(), vec![0, 4, 7]);
+        assert_eq!(triad.prime_form(), vec![0, 3, 7]);
+        assert_eq!(
+            PitchClassSet::from_letters(&[B, D, F, A]).normal_form(),
+            vec![9, 11, 2, 5]
+        );
+
+        // Ties are broken by the span up to the second-last pitch class.
+        assert_eq!(set(&[0, 4, 5, 7, 8]).normal_form(), vec![4, 5, 7, 8, 0]);
+        assert_eq!(set(&[0, 2, 6, 8]).normal_form(), vec![0, 2, 6, 8]);
+        assert_eq!(set(&[3, 7, 11]).normal_form(), vec![3, 7, 11]);
+        assert_eq!(set(&[8, 9, 1]).prime_form(), vec![0, 1, 5]);
+        assert_eq!(set(&[1, 5, 6, 13]).len(), 3);
+        assert_eq!(set(&[10, 11]).to_string(), "{T,E}");
+    }
+
+    #[test]
+    fn forte_numbers() {
+        let forte = |pcs: &[Semitones]| set(pcs).forte_number().unwrap();
+        assert_eq!(forte(&[0, 4, 7]), "3-11");
+        assert_eq!(forte(&[0, 1, 4, 6]), "4-Z15");
+        assert_eq!(forte(&[0, 1, 3, 7]), "4-Z29");
+        assert_eq!(forte(&[0, 2, 4, 5, 7, 9, 11]), "7-35");
+        assert_eq!(forte(&[0, 1, 3, 4, 6, 7, 9, 10]), "8-28");
+        assert_eq!(forte(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]), "12-1");
+        assert_eq!(PitchClassSet::default().forte_number(), None);
+
+        // The list holds every set class of up to six pitch classes once.
+        // Together with their complements, they number all 223 classes of
+        // non-empty sets.
+        let counts: Vec<usize> = FORTE_PRIME_FORMS.iter().map(|c| c.len()).collect();
+        assert_eq!(counts, vec![1, 1, 6, 12, 29, 38, 50]);
+        for (size, classes) in FORTE_PRIME_FORMS.iter().enumerate().skip(1) {
+            for (i, prime) in classes.iter().enumerate() {
+                assert_eq!(prime.len(), size);
+                assert!(classes[..i]
+                    .iter()
+                    .all(|p| !set(p).is_equivalent(&set(prime))));
+
+                let name = |set: &PitchClassSet| {
+                    let z = if set.z_partner().is_some() { "Z" } else { "" };
+                    format!("{}-{}{}", set.len(), z, i + 1)
+                };
+                assert_eq!(forte(prime), name(&set(prime)));
+                // The complement of a hexachord is a hexachord, which has a
+                // number of its own.
+                let complement = set(prime).complement();
+                if size < 6 {
+                    assert_eq!(complement.forte_number().unwrap(), name(&complement));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn equivalence_and_z_relations() {
+        let triad = set(&[0, 4, 7]);
+        assert_eq!(triad.interval_vector(), [0, 0, 1, 1, 1, 0]);
+        assert_eq!(set(&[0, 1, 4, 6]).interval_vector(), [1, 1, 1, 1, 1, 1]);
+        assert_eq!(triad.complement().len(), 9);
+        assert_eq!(triad.complement().forte_number().unwrap(), "9-11");
+
+        assert_eq!(triad.transposition_to(&set(&[2, 6, 9])), Some(2));
+        assert_eq!(triad.transposition_to(&set(&[0, 3, 7])), None);
+        assert_eq!(triad.inversion_to(&set(&[0, 3, 7])), Some(7));
+        assert!(triad.is_equivalent(&set(&[1, 4, 8])));
+        assert!(!triad.is_equivalent(&set(&[0, 4, 8])));
+
+        let all_interval = set(&[0, 1, 4, 6]);
+        assert!(all_interval.is_z_related(&set(&[0, 1, 3, 7])));
+        assert_eq!(all_interval.z_partner(), Some(set(&[0, 1, 3, 7])));
+        assert_eq!(triad.z_partner(), None);
+        let partner = all_interval.complement().z_partner().unwrap();
+        assert_eq!(partner.forte_number().unwrap(), "8-Z29");
+    }
+}
@@ -0,0 +1,260 @@
+use crate::analysis::{detect_keys_weighted, KeyEstimate};
+use crate::music_theory::*;
+use diesel::sqlite::SqliteConnection;
+use std::fmt;
+
+/// A length of time, counted in beats.
+pub type Beats = u32;
+
+/// The number of beats in a bar, and the note value of one beat.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct TimeSignature {
+    pub beats: Beats,
+    pub unit: u32,
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        TimeSignature { beats: 4, unit: 4 }
+    }
+}
+
+impl fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.beats, self.unit)
+    }
+}
+
+/// A sequence of chords, each held for a number of beats, in a time
+/// signature which divides them into bars.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Progression {
+    pub chords: Vec<(Chord, Beats)>,
+    pub time: TimeSignature,
+}
+
+/// Number of bars written on each line of a lead sheet.
+const BARS_PER_LINE: usize = 4;
+
+fn gcd(a: Beats, b: Beats) -> Beats {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Progression {
+    /// Builds a progression from bars written as on a lead sheet. Each bar
+    /// is shared evenly between its slots, and an empty slot holds on to the
+    /// chord before it: `| C . Am . |` in 4/4 is C and Am for two beats
+    /// each. Returns `None` if a bar cannot be shared evenly, is empty, or
+    /// if the first slot is empty.
+    pub fn from_bars(bars: &[Vec<Option<Chord>>], time: TimeSignature) -> Option<Progression> {
+        let mut chords: Vec<(Chord, Beats)> = vec![];
+        for bar in bars {
+            if bar.is_empty() || !time.beats.is_multiple_of(bar.len() as Beats) {
+                return None;
+            }
+            let slot = time.beats / bar.len() as Beats;
+            for chord in bar {
+                match chord {
+                    Some(chord) => chords.push((chord.clone(), slot)),
+                    None => chords.last_mut()?.1 += slot,
+                }
+            }
+        }
+        Some(Progression { chords, time })
+    }
+
+    /// Returns the progression with every chord moved by a number of
+    /// `semitones`.
+    pub fn transposed(&self, semitones: Semitones) -> Progression {
+        Progression {
+            chords: self
+                .chords
+                .iter()
+                .map(|(c, beats)| (c.transposed_semitones(semitones), *beats))
+                .collect(),
+            time: self.time,
+        }
+    }
+
+    /// Returns the beat each chord starts on, counting from 0.
+    pub fn starts(&self) -> Vec<Beats> {
+        self.chords
+            .iter()
+            .scan(0, |beat, (_, length)| {
+                let start = *beat;
+                *beat += length;
+                Some(start)
+            })
+            .collect()
+    }
+
+    /// Returns the total length of the progression in beats.
+    pub fn length(&self) -> Beats {
+        self.chords.iter().map(|(_, beats)| beats).sum()
+    }
+
+    /// Ranks the keys the progression could be in, with the most likely
+    /// first. Each chord counts in proportion to its length.
+    pub fn detect_keys(&self) -> Vec<KeyEstimate> {
+        let (chords, beats): (Vec<Chord>, Vec<f64>) = self
+            .chords
+            .iter()
+            .map(|(chord, beats)| (chord.clone(), *beats as f64))
+            .unzip();
+        detect_keys_weighted(&chords, &beats)
+    }
+
+    /// Returns every bar as a list of slots of equal length, as few as are
+    /// needed to show where each chord starts. Slots in which no chord
+    /// starts are `None`.
+    pub fn bars(&self) -> Vec<Vec<Option<&Chord>>> {
+        let bar_length = self.time.beats;
+        let bar_count = self.length().div_ceil(bar_length);
+        let starts = self.starts();
+
+        (0..bar_count)
+            .map(|bar| {
+                let first = bar * bar_length;
+                let in_bar: Vec<(Beats, &Chord)> = starts
+                    .iter()
+                    .zip(&self.chords)
+                    .filter(|(start, _)| (first..first + bar_length).contains(*start))
+                    .map(|(start, (chord, _))| (start - first, chord))
+                    .collect();
+                let slot = in_bar
+                    .iter()
+                    .fold(bar_length, |slot, (offset, _)| gcd(slot, *offset));
+                (0..bar_length / slot)
+                    .map(|i| {
+                        in_bar
+                            .iter()
+                            .find(|(offset, _)| *offset == i * slot)
+                            .map(|(_, chord)| *chord)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Writes the progression as a lead sheet, e.g. `| C . Am . | F G7 |`,
+    /// four bars to a line. Bar lines are lined up from one line to the
+    /// next.
+    pub fn lead_sheet(&self, style: &NotationStyle, conn: &SqliteConnection) -> String {
+        let bars: Vec<Vec<String>> = self
+            .bars()
+            .iter()
+            .map(|bar| {
+                bar.iter()
+                    .map(|slot| match slot {
+                        Some(chord) => chord.symbol(style, conn),
+                        None => ".".to_owned(),
+                    })
+                    .collect()
+            })
+            .collect();
+        let width = bars
+            .iter()
+            .flatten()
+            .map(|s| s.chars().count())
+            .max()
+            .unwrap_or(0);
+        let bars: Vec<String> = bars
+            .iter()
+            .map(|bar| {
+                bar.iter()
+                    .map(|s| format!("{:1$}", s, width))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        let bar_width = bars.iter().map(|b| b.chars().count()).max().unwrap_or(0);
+
+        bars.chunks(BARS_PER_LINE)
+            .map(|line| {
+                let line: String = line
+                    .iter()
+                    .map(|bar| format!("| {:1$} ", bar, bar_width))
+                    .collect();
+                line + "|"
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chord_library;
+    use crate::database::initialise_database;
+    use crate::music_theory::tests::chord;
+    use intervals::*;
+    use Letter::*;
+
+    #[test]
+    fn durations_and_bars() {
+        let major = vec![Maj3rd, Per5th];
+        let minor = vec![Min3rd, Per5th];
+        let bars = vec![
+            vec![
+                Some(chord(C, major.clone())),
+                None,
+                Some(chord(A, minor.clone())),
+                None,
+            ],
+            vec![
+                Some(chord(F, major.clone())),
+                Some(chord(G, vec![Maj3rd, Per5th, Min7th])),
+            ],
+            vec![Some(chord(C, major.clone()))],
+        ];
+        let progression = Progression::from_bars(&bars, TimeSignature::default()).unwrap();
+
+        let beats: Vec<Beats> = progression.chords.iter().map(|(_, b)| *b).collect();
+        assert_eq!(beats, vec![2, 2, 2, 2, 4]);
+        assert_eq!(progression.starts(), vec![0, 2, 4, 6, 8]);
+        assert_eq!(progression.length(), 12);
+        let slots: Vec<usize> = progression.bars().iter().map(|b| b.len()).collect();
+        assert_eq!(slots, vec![2, 2, 1]);
+
+        let conn = initialise_database().unwrap();
+        chord_library::populate_database(&conn);
+        let style = NotationStyle::default();
+        assert_eq!(
+            progression.lead_sheet(&style, &conn),
+            "| C  Am | F  G7 | C     |"
+        );
+        assert_eq!(
+            progression.transposed(2).lead_sheet(&style, &conn),
+            "| D  Bm | G  A7 | D     |"
+        );
+        assert_eq!(progression.detect_keys()[0].key, Key::major(C));
+
+        // Chords can be held across bar lines, shown by an empty slot.
+        let waltz = TimeSignature { beats: 3, unit: 4 };
+        let bars = vec![
+            vec![
+                Some(chord(D, minor.clone())),
+                None,
+                Some(chord(G, major.clone())),
+            ],
+            vec![None, None, None],
+        ];
+        let progression = Progression::from_bars(&bars, waltz).unwrap();
+        assert_eq!(
+            progression.lead_sheet(&style, &conn),
+            "| Dm .  G  | .        |"
+        );
+
+        // Bars must be shared evenly, and cannot start with an empty slot.
+        assert_eq!(
+            Progression::from_bars(&bars, TimeSignature::default()),
+            None
+        );
+        assert_eq!(Progression::from_bars(&[vec![None]], waltz), None);
+    }
+}
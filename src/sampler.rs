@@ -0,0 +1,406 @@
+use crate::sequencer::{timeline, Bpm, Event, Message};
+use dasp::interpolate::linear::Linear;
+use dasp::signal::interpolate::Converter;
+use dasp::{signal, Signal};
+use pitch_calc::{Letter, LetterOctave};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A single frame of stereo audio: the left and right samples.
+pub type Frame = [f32; 2];
+
+/// Sample rate of the audio produced by the sampler, in frames per second.
+pub const SAMPLE_RATE: f64 = 44_100.0;
+/// How loudly every note is played, from 0 to 1.
+pub const NOTE_VELOCITY: f32 = 0.6;
+/// The most notes that can sound at once. Playing another note cuts off the
+/// oldest one.
+pub const VOICES: usize = 12;
+/// How long notes take to fade out once they are released.
+pub const RELEASE: Duration = Duration::from_millis(20);
+
+/// The instruments whose samples live in the assets folder.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum Instrument {
+    #[default]
+    CasioPiano,
+    ThumbPiano,
+}
+
+impl Instrument {
+    /// Name of the instrument's WAV file in the assets folder.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Instrument::CasioPiano => "Casio Piano C5.wav",
+            Instrument::ThumbPiano => "thumbpiano A#3.wav",
+        }
+    }
+
+    /// The note recorded in the instrument's sample.
+    pub fn note(self) -> LetterOctave {
+        match self {
+            Instrument::CasioPiano => LetterOctave(Letter::C, 5),
+            Instrument::ThumbPiano => LetterOctave(Letter::Bb, 3),
+        }
+    }
+
+    /// Loads the instrument's sample from the assets folder.
+    pub fn load(self) -> Result<Sample, Box<dyn Error>> {
+        let path = assets()?.join(self.file_name());
+        Ok(Sample::from_wav_file(path, self.note())?)
+    }
+}
+
+impl FromStr for Instrument {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "piano" => Ok(Instrument::CasioPiano),
+            "thumb" | "thumbpiano" => Ok(Instrument::ThumbPiano),
+            _ => Err(format!("Unknown instrument {}, try piano or thumb", s)),
+        }
+    }
+}
+
+/// Finds the assets folder, looking around the current directory first and
+/// then in the directory the crate was built from.
+fn assets() -> Result<PathBuf, find_folder::Error> {
+    find_folder::Search::ParentsThenKids(5, 5)
+        .for_folder("assets")
+        .or_else(|_| {
+            find_folder::Search::Kids(1)
+                .of(PathBuf::from(env!("CARGO_MANIFEST_DIR")))
+                .for_folder("assets")
+        })
+}
+
+/// A recording of a single note, which the sampler plays back faster or
+/// slower to play other notes.
+#[derive(Clone, Debug)]
+pub struct Sample {
+    frames: Arc<[Frame]>,
+    /// Sample rate of the recording, in frames per second.
+    rate: f64,
+    /// Pitch of the recorded note.
+    hz: f64,
+}
+
+impl Sample {
+    /// Creates a sample out of frames recorded at `rate` frames per second,
+    /// playing the given note.
+    pub fn new(frames: Vec<Frame>, rate: f64, note: LetterOctave) -> Sample {
+        Sample {
+            frames: frames.into(),
+            rate,
+            hz: note.to_hz().hz() as f64,
+        }
+    }
+
+    /// Reads a sample from a mono or stereo WAV file which plays the given
+    /// note. Files with more than two channels only keep the first two.
+    pub fn from_wav_file<P: AsRef<Path>>(
+        path: P,
+        note: LetterOctave,
+    ) -> Result<Sample, hound::Error> {
+        let mut reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+        let samples: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|s| s.map(|s| s as f32 / scale))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        let frames = samples
+            .chunks_exact(spec.channels as usize)
+            .map(|c| [c[0], c[c.len().min(2) - 1]])
+            .collect();
+        Ok(Sample::new(frames, spec.sample_rate as f64, note))
+    }
+
+    /// Returns the length of the recording.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frames.len() as f64 / self.rate)
+    }
+}
+
+/// Iterates over the frames of a sample without copying them.
+struct Frames {
+    frames: Arc<[Frame]>,
+    index: usize,
+}
+
+impl Iterator for Frames {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        let frame = self.frames.get(self.index).copied();
+        self.index += 1;
+        frame
+    }
+}
+
+/// A note being played by the sampler.
+struct Voice {
+    note: LetterOctave,
+    signal: Converter<signal::FromIterator<Frames>, Linear<Frame>>,
+    /// Number of frames left before the note has faded out, once released.
+    release: Option<usize>,
+}
+
+/// Plays a sample at any pitch, several notes at once.
+pub struct Sampler {
+    sample: Sample,
+    /// Sample rate of the audio produced, in frames per second.
+    rate: f64,
+    voices: Vec<Voice>,
+}
+
+impl Sampler {
+    /// Creates a sampler which plays the sample, producing audio at `rate`
+    /// frames per second.
+    pub fn new(sample: Sample, rate: f64) -> Sampler {
+        Sampler {
+            sample,
+            rate,
+            voices: vec![],
+        }
+    }
+
+    /// Starts playing the note.
+    pub fn note_on(&mut self, note: LetterOctave) {
+        if self.voices.len() >= VOICES {
+            self.voices.remove(0);
+        }
+        let mut source = signal::from_iter(Frames {
+            frames: self.sample.frames.clone(),
+            index: 0,
+        });
+        let interpolator = Linear::new(source.next(), source.next());
+        // Playing the sample back faster raises its pitch.
+        let speed = note.to_hz().hz() as f64 / self.sample.hz;
+        self.voices.push(Voice {
+            note,
+            signal: Converter::from_hz_to_hz(
+                source,
+                interpolator,
+                self.sample.rate * speed,
+                self.rate,
+            ),
+            release: None,
+        });
+    }
+
+    /// Lets the note fade out.
+    pub fn note_off(&mut self, note: LetterOctave) {
+        let release = (RELEASE.as_secs_f64() * self.rate) as usize;
+        for voice in self.voices.iter_mut() {
+            if voice.note == note && voice.release.is_none() {
+                voice.release = Some(release);
+            }
+        }
+    }
+
+    /// Silences every note at once.
+    pub fn stop(&mut self) {
+        self.voices.clear();
+    }
+
+    /// Acts on a message from the sequencer.
+    pub fn handle(&mut self, message: Message) {
+        match message {
+            Message::NoteOn(note) => self.note_on(note),
+            Message::NoteOff(note) => self.note_off(note),
+            Message::Stop => self.stop(),
+            Message::Tempo(_) => {}
+        }
+    }
+
+    /// Returns true if no notes are sounding.
+    pub fn is_silent(&self) -> bool {
+        self.voices.is_empty()
+    }
+
+    /// Adds the sound of every note playing to the buffer, and moves on by
+    /// the length of the buffer.
+    pub fn fill_slice(&mut self, buffer: &mut [Frame]) {
+        let release = (RELEASE.as_secs_f64() * self.rate).max(1.0) as f32;
+        for voice in self.voices.iter_mut() {
+            for frame in buffer.iter_mut() {
+                let gain = match voice.release {
+                    Some(0) => break,
+                    Some(left) => {
+                        voice.release = Some(left - 1);
+                        NOTE_VELOCITY * left as f32 / release
+                    }
+                    None => NOTE_VELOCITY,
+                };
+                let [left, right] = voice.signal.next();
+                frame[0] += left * gain;
+                frame[1] += right * gain;
+            }
+        }
+        self.voices
+            .retain(|v| v.release != Some(0) && !v.signal.is_exhausted());
+    }
+}
+
+/// Plays the events through a sampler and returns the audio produced. Every
+/// event's delay is counted from the start, as though they had all been
+/// sent to a sequencer started at the given tempo at once. The audio goes
+/// on until the last note has faded out.
+pub fn render(events: &[Event], tempo: Bpm, sample: &Sample, rate: f64) -> Vec<Frame> {
+    let mut sampler = Sampler::new(sample.clone(), rate);
+    let mut frames: Vec<Frame> = vec![];
+    for (time, message) in timeline(events, tempo) {
+        let end = (time.as_secs_f64() * rate).round() as usize;
+        if end > frames.len() {
+            let start = frames.len();
+            frames.resize(end, [0.0; 2]);
+            sampler.fill_slice(&mut frames[start..]);
+        }
+        sampler.handle(message);
+    }
+
+    let block = rate as usize / 10;
+    while !sampler.is_silent() {
+        let start = frames.len();
+        frames.resize(start + block, [0.0; 2]);
+        sampler.fill_slice(&mut frames[start..]);
+    }
+    frames
+}
+
+/// Writes the audio to a 16-bit stereo WAV file, clipping any samples which
+/// are too loud.
+pub fn write_wav<P: AsRef<Path>>(path: P, frames: &[Frame], rate: f64) -> Result<(), hound::Error> {
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate: rate as u32,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for sample in frames.iter().flatten() {
+        writer.write_sample(to_i16(*sample))?;
+    }
+    writer.finalize()
+}
+
+/// Converts a sample to 16 bits, clipping it if it is too loud.
+pub(crate) fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+/// Plays the events on the instrument, starting at the given tempo, and
+/// writes the audio to a WAV file.
+pub fn render_to_wav<P: AsRef<Path>>(
+    path: P,
+    events: &[Event],
+    tempo: Bpm,
+    instrument: Instrument,
+) -> Result<(), Box<dyn Error>> {
+    let sample = instrument.load()?;
+    let frames = render(events, tempo, &sample, SAMPLE_RATE);
+    write_wav(path, &frames, SAMPLE_RATE)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequencer::{note_events, PPQ};
+    use Letter::*;
+
+    /// A sine wave playing A4 for a second.
+    fn sine() -> Sample {
+        let rate = 8_000.0;
+        let frames = (0..8_000)
+            .map(|i| {
+                let s = (i as f64 * 440.0 * 2.0 * std::f64::consts::PI / rate).sin() as f32;
+                [s, s]
+            })
+            .collect();
+        Sample::new(frames, rate, LetterOctave(A, 4))
+    }
+
+    /// Counts the times the left channel goes from negative to positive.
+    fn crossings(frames: &[Frame]) -> usize {
+        frames
+            .windows(2)
+            .filter(|w| w[0][0] < 0.0 && w[1][0] >= 0.0)
+            .count()
+    }
+
+    #[test]
+    fn pitch_shifting() {
+        let sample = sine();
+        let mut sampler = Sampler::new(sample, 8_000.0);
+        let mut buffer = vec![[0.0; 2]; 4_000];
+
+        // Half a second of A5 holds twice as many cycles as of A4.
+        sampler.note_on(LetterOctave(A, 5));
+        sampler.fill_slice(&mut buffer);
+        let cycles = crossings(&buffer);
+        assert!((438..=442).contains(&cycles), "{} cycles", cycles);
+
+        // Two notes sound together, until the sample runs out.
+        sampler.stop();
+        sampler.note_on(LetterOctave(A, 4));
+        sampler.note_on(LetterOctave(E, 5));
+        sampler.fill_slice(&mut vec![[0.0; 2]; 100]);
+        assert_eq!(sampler.voices.len(), 2);
+        sampler.fill_slice(&mut vec![[0.0; 2]; 8_000]);
+        assert!(sampler.is_silent());
+    }
+
+    #[test]
+    fn rendering() {
+        let sample = sine();
+        let rate = 8_000.0;
+        let notes = [LetterOctave(C, 4), LetterOctave(E, 4), LetterOctave(G, 4)];
+        // Half a beat of silence, then the chord for a beat.
+        let events = note_events(&notes, PPQ / 2, PPQ);
+        let frames = render(&events, 120, &sample, rate);
+
+        // Silence, then the chord, then a short release.
+        let release = (RELEASE.as_secs_f64() * rate) as usize;
+        assert_eq!(frames.len(), 6_800);
+        assert!(frames[..2_000].iter().all(|f| *f == [0.0; 2]));
+        assert!(frames[2_000..6_000].iter().any(|f| f[0].abs() > 0.5));
+        assert!(frames[6_000 + release..].iter().all(|f| *f == [0.0; 2]));
+
+        // The recorded note is played back unchanged, apart from its volume.
+        let events = note_events(&[LetterOctave(A, 4)], 0, PPQ);
+        let frames = render(&events, 120, &sample, rate);
+        let expected = sine().frames[100][0] * NOTE_VELOCITY;
+        assert!((frames[100][0] - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn wav_files() {
+        let piano = Instrument::CasioPiano.load().unwrap();
+        assert!(piano.duration() > Duration::from_secs(1));
+        assert!(Instrument::ThumbPiano.load().is_ok());
+        assert_eq!("thumb".parse(), Ok(Instrument::ThumbPiano));
+
+        let path = std::env::temp_dir().join(format!(
+            "harmony_explorer_sampler_test_{}.wav",
+            std::process::id()
+        ));
+        let events = note_events(&[LetterOctave(C, 4)], 0, PPQ / 4);
+        render_to_wav(&path, &events, 120, Instrument::CasioPiano).unwrap();
+
+        let written = Sample::from_wav_file(&path, LetterOctave(C, 4)).unwrap();
+        assert_eq!(written.rate, SAMPLE_RATE);
+        assert!(written.frames.iter().any(|f| f[0].abs() > 0.01));
+        std::fs::remove_file(path).unwrap();
+    }
+}
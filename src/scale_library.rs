@@ -0,0 +1,223 @@
+use crate::database::*;
+use crate::music_theory::*;
+use crate::schema::*;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+
+pub fn generate_scales() -> Vec<ScaleNote> {
+    use intervals::*;
+    let scales: Vec<(&str, Quality)> = vec![
+        // Modes of the major scale
+        (
+            "major",
+            vec![Maj2nd, Maj3rd, Per4th, Per5th, Maj6th, Maj7th],
+        ),
+        (
+            "dorian",
+            vec![Maj2nd, Min3rd, Per4th, Per5th, Maj6th, Min7th],
+        ),
+        (
+            "phrygian",
+            vec![Min2nd, Min3rd, Per4th, Per5th, Min6th, Min7th],
+        ),
+        (
+            "lydian",
+            vec![Maj2nd, Maj3rd, Aug4th, Per5th, Maj6th, Maj7th],
+        ),
+        (
+            "mixolydian",
+            vec![Maj2nd, Maj3rd, Per4th, Per5th, Maj6th, Min7th],
+        ),
+        (
+            "natural minor",
+            vec![Maj2nd, Min3rd, Per4th, Per5th, Min6th, Min7th],
+        ),
+        (
+            "locrian",
+            vec![Min2nd, Min3rd, Per4th, Dim5th, Min6th, Min7th],
+        ),
+        // Modes of the harmonic minor scale
+        (
+            "harmonic minor",
+            vec![Maj2nd, Min3rd, Per4th, Per5th, Min6th, Maj7th],
+        ),
+        (
+            "locrian natural 6",
+            vec![Min2nd, Min3rd, Per4th, Dim5th, Maj6th, Min7th],
+        ),
+        (
+            "ionian augmented",
+            vec![Maj2nd, Maj3rd, Per4th, Aug5th, Maj6th, Maj7th],
+        ),
+        (
+            "ukrainian dorian",
+            vec![Maj2nd, Min3rd, Aug4th, Per5th, Maj6th, Min7th],
+        ),
+        (
+            "phrygian dominant",
+            vec![Min2nd, Maj3rd, Per4th, Per5th, Min6th, Min7th],
+        ),
+        (
+            "lydian sharp 2",
+            vec![Aug2nd, Maj3rd, Aug4th, Per5th, Maj6th, Maj7th],
+        ),
+        (
+            "ultralocrian",
+            vec![Min2nd, Min3rd, Dim4th, Dim5th, Min6th, Dim7th],
+        ),
+        // Modes of the melodic minor scale
+        (
+            "melodic minor",
+            vec![Maj2nd, Min3rd, Per4th, Per5th, Maj6th, Maj7th],
+        ),
+        (
+            "dorian flat 2",
+            vec![Min2nd, Min3rd, Per4th, Per5th, Maj6th, Min7th],
+        ),
+        (
+            "lydian augmented",
+            vec![Maj2nd, Maj3rd, Aug4th, Aug5th, Maj6th, Maj7th],
+        ),
+        (
+            "lydian dominant",
+            vec![Maj2nd, Maj3rd, Aug4th, Per5th, Maj6th, Min7th],
+        ),
+        (
+            "mixolydian flat 6",
+            vec![Maj2nd, Maj3rd, Per4th, Per5th, Min6th, Min7th],
+        ),
+        (
+            "locrian natural 2",
+            vec![Maj2nd, Min3rd, Per4th, Dim5th, Min6th, Min7th],
+        ),
+        (
+            "altered",
+            vec![Min2nd, Min3rd, Dim4th, Dim5th, Min6th, Min7th],
+        ),
+        // Pentatonic & blues scales
+        ("major pentatonic", vec![Maj2nd, Maj3rd, Per5th, Maj6th]),
+        ("minor pentatonic", vec![Min3rd, Per4th, Per5th, Min7th]),
+        ("blues", vec![Min3rd, Per4th, Dim5th, Per5th, Min7th]),
+        ("major blues", vec![Maj2nd, Min3rd, Maj3rd, Per5th, Maj6th]),
+        // Symmetrical scales
+        ("whole tone", vec![Maj2nd, Maj3rd, Aug4th, Aug5th, Min7th]),
+        (
+            "diminished",
+            vec![Maj2nd, Min3rd, Per4th, Dim5th, Aug5th, Maj6th, Maj7th],
+        ),
+        (
+            "dominant diminished",
+            vec![Min2nd, Aug2nd, Maj3rd, Aug4th, Per5th, Maj6th, Min7th],
+        ),
+        // Bebop scales
+        (
+            "bebop dominant",
+            vec![Maj2nd, Maj3rd, Per4th, Per5th, Maj6th, Min7th, Maj7th],
+        ),
+        (
+            "bebop major",
+            vec![Maj2nd, Maj3rd, Per4th, Per5th, Aug5th, Maj6th, Maj7th],
+        ),
+        (
+            "bebop dorian",
+            vec![Maj2nd, Min3rd, Maj3rd, Per4th, Per5th, Maj6th, Min7th],
+        ),
+        (
+            "bebop melodic minor",
+            vec![Maj2nd, Min3rd, Per4th, Per5th, Aug5th, Maj6th, Maj7th],
+        ),
+    ];
+
+    scales
+        .into_iter()
+        .flat_map(|(scale, pattern)| {
+            pattern
+                .into_iter()
+                .map(move |interval| ScaleNote::note(scale, interval))
+        })
+        .collect()
+}
+
+pub fn generate_scale_names() -> Vec<ScaleName> {
+    vec![
+        ("major", "ionian"),
+        ("natural minor", "minor"),
+        ("natural minor", "aeolian"),
+        ("locrian natural 6", "locrian #6"),
+        ("ionian augmented", "ionian #5"),
+        ("ukrainian dorian", "dorian #4"),
+        ("phrygian dominant", "spanish"),
+        ("lydian sharp 2", "lydian #2"),
+        ("ultralocrian", "super locrian bb7"),
+        ("melodic minor", "jazz minor"),
+        ("dorian flat 2", "dorian b2"),
+        ("dorian flat 2", "phrygian #6"),
+        ("lydian augmented", "lydian #5"),
+        ("lydian dominant", "overtone"),
+        ("lydian dominant", "lydian b7"),
+        ("mixolydian flat 6", "mixolydian b6"),
+        ("mixolydian flat 6", "aeolian dominant"),
+        ("locrian natural 2", "locrian #2"),
+        ("locrian natural 2", "half diminished"),
+        ("altered", "super locrian"),
+        ("blues", "minor blues"),
+        ("diminished", "whole half"),
+        ("diminished", "whole-half"),
+        ("dominant diminished", "half whole"),
+        ("dominant diminished", "half-whole"),
+        ("whole tone", "whole-tone"),
+    ]
+    .into_iter()
+    .map(|t| ScaleName::name(t.0, t.1))
+    .collect()
+}
+
+pub fn populate_database(db: &SqliteConnection) {
+    diesel::insert_into(scale_notes::table)
+        .values(generate_scales())
+        .execute(db)
+        .unwrap();
+
+    diesel::insert_into(scale_names::table)
+        .values(generate_scale_names())
+        .execute(db)
+        .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_lookup() {
+        let db = initialise_database().unwrap();
+        populate_database(&db);
+
+        let d_dorian = Scale {
+            root: LetterOctave(Letter::D, 4),
+            pattern: get_scale("dorian", &db).unwrap(),
+        };
+        assert_eq!(d_dorian.to_string(), "D4 E4 F4 G4 A4 B4 C5");
+
+        let a_minor = Scale {
+            root: LetterOctave(Letter::A, 3),
+            pattern: get_scale("aeolian", &db).unwrap(),
+        };
+        assert_eq!(a_minor.to_string(), "A3 B3 C4 D4 E4 F4 G4");
+
+        let c_altered = Scale {
+            root: LetterOctave(Letter::C, 4),
+            pattern: get_scale("super locrian", &db).unwrap(),
+        };
+        assert_eq!(c_altered.to_string(), "C4 Db4 Eb4 Fb4 Gb4 Ab4 Bb4");
+
+        let g_bebop = Scale {
+            root: LetterOctave(Letter::G, 4),
+            pattern: get_scale("bebop dominant", &db).unwrap(),
+        };
+        assert_eq!(g_bebop.notes().len(), 8);
+        assert!(g_bebop.contains(Letter::F) && g_bebop.contains(Letter::Fsh));
+
+        assert_eq!(get_scale("not a scale", &db), None);
+    }
+}
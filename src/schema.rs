@@ -2,6 +2,8 @@ table! {
     names (chord) {
         chord -> Text,
         alternative_name -> Text,
+        preferred -> Bool,
+        style -> Text,
     }
 }
 
@@ -13,4 +15,19 @@ table! {
     }
 }
 
-allow_tables_to_appear_in_same_query!(names, notes,);
+table! {
+    scale_names (alternative_name) {
+        scale -> Text,
+        alternative_name -> Text,
+    }
+}
+
+table! {
+    scale_notes (scale, interval) {
+        scale -> Text,
+        degree -> Integer,
+        interval -> Integer,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(names, notes, scale_names, scale_notes,);
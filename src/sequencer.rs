@@ -5,30 +5,157 @@ use std::option::Option;
 use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
 use std::time::{Duration, Instant};
 
+/// A position or a length in time, counted in ticks. There are `PPQ` ticks
+/// in every beat.
+pub type Ticks = u64;
+/// A tempo, in beats per minute.
+pub type Bpm = u32;
+
+/// Pulses per quarter note: the number of ticks in a beat.
+pub const PPQ: Ticks = 480;
+/// The tempo the sequencer starts at, unless told otherwise.
+pub const DEFAULT_TEMPO: Bpm = 120;
+
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
 #[allow(dead_code)]
 pub enum Message {
     NoteOn(LetterOctave),
     NoteOff(LetterOctave),
     Stop,
+    /// Changes the tempo from the moment the message is due. Events which
+    /// are due later are brought forward or pushed back to suit.
+    Tempo(Bpm),
 }
 
+/// A message to be sent once `del` ticks have passed since the sequencer
+/// received it.
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub struct Event {
     pub msg: Message,
-    pub del: Duration,
+    pub del: Ticks,
+}
+
+/// The tempo at every point in time, as a list of tempo changes. A tempo of
+/// 0 would never reach the next tick, so it is taken as 1 beat per minute.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TempoMap {
+    /// The tick at which each tempo starts, in order. The first starts at
+    /// tick 0.
+    changes: Vec<(Ticks, Bpm)>,
+}
+
+impl TempoMap {
+    /// Creates a map which keeps the same tempo throughout.
+    pub fn new(tempo: Bpm) -> TempoMap {
+        TempoMap {
+            changes: vec![(0, tempo.max(1))],
+        }
+    }
+
+    /// Changes the tempo from the given tick until the next tempo change.
+    pub fn set_tempo(&mut self, tick: Ticks, tempo: Bpm) {
+        let tempo = tempo.max(1);
+        match self.changes.binary_search_by_key(&tick, |(t, _)| *t) {
+            Ok(i) => self.changes[i].1 = tempo,
+            Err(i) => self.changes.insert(i, (tick, tempo)),
+        }
+    }
+
+    /// Returns the tempo at the given tick.
+    pub fn tempo_at(&self, tick: Ticks) -> Bpm {
+        self.changes
+            .iter()
+            .take_while(|(t, _)| *t <= tick)
+            .last()
+            .map(|(_, tempo)| *tempo)
+            .unwrap()
+    }
+
+    /// Returns the time from the start until the given tick.
+    pub fn to_duration(&self, tick: Ticks) -> Duration {
+        let mut nanos = 0;
+        for (i, (start, tempo)) in self.changes.iter().enumerate() {
+            if *start >= tick {
+                break;
+            }
+            let end = self.changes.get(i + 1).map_or(tick, |(t, _)| *t.min(&tick));
+            nanos += ticks_to_nanos(end - start, *tempo);
+        }
+        Duration::from_nanos(nanos as u64)
+    }
+
+    /// Returns the last tick reached once the given time has passed since
+    /// the start.
+    pub fn to_ticks(&self, time: Duration) -> Ticks {
+        let mut left = time.as_nanos();
+        for (i, (start, tempo)) in self.changes.iter().enumerate() {
+            let length = self.changes.get(i + 1).map(|(t, _)| t - start);
+            match length {
+                Some(ticks) if ticks_to_nanos(ticks, *tempo) <= left => {
+                    left -= ticks_to_nanos(ticks, *tempo);
+                }
+                _ => {
+                    let ticks = left * (*tempo as u128) * (PPQ as u128) / 60_000_000_000;
+                    return start + ticks as Ticks;
+                }
+            }
+        }
+        unreachable!()
+    }
+}
+
+/// Returns the number of nanoseconds taken by the ticks at the tempo,
+/// rounded up so that the last tick has been reached once they have passed.
+fn ticks_to_nanos(ticks: Ticks, tempo: Bpm) -> u128 {
+    let per_minute = tempo as u128 * PPQ as u128;
+    (ticks as u128 * 60_000_000_000).div_ceil(per_minute)
+}
+
+/// Returns the messages of the events along with the time at which each is
+/// due, soonest first, as though they had all been sent to a sequencer
+/// started at the given tempo. Events due at the same time keep their
+/// order.
+pub fn timeline(events: &[Event], tempo: Bpm) -> Vec<(Duration, Message)> {
+    let (tx, mut sequencer) = VirtualSequencer::new(tempo);
+    for event in events {
+        tx.send(*event);
+    }
+    sequencer.finish()
+}
+
+/// Returns the events which play the notes together after `delay` and hold
+/// them for `length`.
+pub fn note_events(notes: &[LetterOctave], delay: Ticks, length: Ticks) -> Vec<Event> {
+    notes
+        .iter()
+        .flat_map(|note| {
+            vec![
+                Event {
+                    msg: Message::NoteOn(*note),
+                    del: delay,
+                },
+                Event {
+                    msg: Message::NoteOff(*note),
+                    del: delay + length,
+                },
+            ]
+        })
+        .collect()
 }
 
 #[derive(PartialEq, Eq)]
 struct EventAbs {
     msg: Message,
-    ins: Instant,
+    tick: Ticks,
+    /// Number of events received before this one, so that events due at
+    /// the same tick are sent in the order they were received.
+    order: u64,
 }
 
 impl Ord for EventAbs {
     fn cmp(&self, other: &EventAbs) -> Ordering {
         // sooner events come first
-        return other.ins.cmp(&self.ins);
+        (other.tick, other.order).cmp(&(self.tick, self.order))
     }
 }
 
@@ -38,65 +165,417 @@ impl PartialOrd for EventAbs {
     }
 }
 
-#[allow(dead_code)]
-fn rel_to_abs(rel: Event) -> EventAbs {
-    EventAbs {
-        msg: rel.msg,
-        ins: Instant::now() + rel.del,
+/// An order which the sequencer carries out as soon as it receives it,
+/// rather than when it is due.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+enum Control {
+    Pause,
+    Resume,
+    Clear,
+    Panic,
+    Seek(Ticks),
+}
+
+enum Input {
+    Event(Event),
+    Control(Control),
+}
+
+#[derive(Clone)]
+enum Target {
+    Schedule(Sender<Input>),
+    Recorder(Sender<Event>),
+}
+
+/// A handle to a running sequencer, through which events are scheduled and
+/// the transport is controlled. Clones of the handle control the same
+/// sequencer. Once the sequencer has stopped, the handle does nothing.
+#[derive(Clone)]
+pub struct Sequencer {
+    target: Target,
+}
+
+impl Sequencer {
+    /// Returns a handle which passes the events on to the receiver instead
+    /// of playing them, e.g. so that they can be rendered to a file. The
+    /// transport controls are ignored.
+    pub fn recorder() -> (Sequencer, Receiver<Event>) {
+        let (tx, rx) = channel();
+        let recorder = Sequencer {
+            target: Target::Recorder(tx),
+        };
+        (recorder, rx)
+    }
+
+    /// Schedules the event, counting its delay from the current position.
+    pub fn send(&self, event: Event) {
+        // Nobody is listening once the sequencer has stopped, so the event
+        // can be dropped.
+        match &self.target {
+            Target::Schedule(tx) => {
+                let _ = tx.send(Input::Event(event));
+            }
+            Target::Recorder(tx) => {
+                let _ = tx.send(event);
+            }
+        }
+    }
+
+    /// Stops the clock and releases the notes which are sounding. Nothing is
+    /// sent until the sequencer is resumed, and events scheduled in the
+    /// meantime are counted from the point where it paused.
+    pub fn pause(&self) {
+        self.control(Control::Pause);
+    }
+
+    /// Starts the clock again from where it was paused.
+    pub fn resume(&self) {
+        self.control(Control::Resume);
+    }
+
+    /// Forgets the events which are waiting to be sent, and releases the
+    /// notes which are sounding.
+    pub fn clear(&self) {
+        self.control(Control::Clear);
+    }
+
+    /// Forgets the events which are waiting to be sent, and silences every
+    /// note straight away.
+    pub fn panic(&self) {
+        self.control(Control::Panic);
+    }
+
+    /// Moves the clock to the given tick, counted from when the sequencer
+    /// started. Events due before it are skipped, and the notes which are
+    /// sounding are released.
+    pub fn seek(&self, tick: Ticks) {
+        self.control(Control::Seek(tick));
+    }
+
+    fn control(&self, control: Control) {
+        if let Target::Schedule(tx) = &self.target {
+            let _ = tx.send(Input::Control(control));
+        }
     }
 }
 
-pub fn start() -> (Sender<Event>, Receiver<Message>) {
+/// A source of time for the sequencer.
+pub trait Clock {
+    /// Returns the time passed since the clock started.
+    fn now(&self) -> Duration;
+}
+
+/// A clock which keeps real time.
+pub struct RealTime {
+    start: Instant,
+}
+
+impl RealTime {
+    pub fn new() -> RealTime {
+        RealTime {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for RealTime {
+    fn default() -> RealTime {
+        RealTime::new()
+    }
+}
+
+impl Clock for RealTime {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A clock which only moves when it is told to.
+#[derive(Default)]
+pub struct VirtualClock {
+    now: Duration,
+}
+
+impl VirtualClock {
+    /// Moves the clock to the given time, unless it is already past it.
+    pub fn set(&mut self, time: Duration) {
+        self.now = self.now.max(time);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        self.now
+    }
+}
+
+/// The state of a sequencer: the events waiting to be sent, the tempo map
+/// which decides when they are due, and the position of the clock.
+struct Schedule<C: Clock> {
+    // Heap of events that are awaiting processing. The soonest event that must be processed
+    // sits at the top of the heap
+    heap: BinaryHeap<EventAbs>,
+    tempo: TempoMap,
+    clock: C,
+    /// A tick, and the time at which the clock passed it, from which the
+    /// clock counts.
+    anchor: (Duration, Ticks),
+    /// The tick at which the clock was paused, if it is.
+    paused: Option<Ticks>,
+    /// The notes which have been turned on and not yet off.
+    sounding: Vec<LetterOctave>,
+    received: u64,
+    /// The messages sent, waiting to be passed on.
+    sent: Vec<Message>,
+}
+
+impl<C: Clock> Schedule<C> {
+    fn new(tempo: Bpm, clock: C) -> Schedule<C> {
+        Schedule {
+            heap: BinaryHeap::new(),
+            tempo: TempoMap::new(tempo),
+            anchor: (clock.now(), 0),
+            clock,
+            paused: None,
+            sounding: vec![],
+            received: 0,
+            sent: vec![],
+        }
+    }
+
+    /// Returns the tick the clock has reached.
+    fn position(&self) -> Ticks {
+        match self.paused {
+            Some(tick) => tick,
+            None => {
+                let (time, tick) = self.anchor;
+                let since = self.clock.now().saturating_sub(time);
+                self.tempo.to_ticks(self.tempo.to_duration(tick) + since)
+            }
+        }
+    }
+
+    /// Returns the time at which the tick happens, unless the clock is
+    /// paused.
+    fn time(&self, tick: Ticks) -> Duration {
+        let (time, start) = self.anchor;
+        let since = self.tempo.to_duration(tick);
+        time + since.saturating_sub(self.tempo.to_duration(start))
+    }
+
+    /// Returns how long until the soonest event is due, or `None` if there
+    /// are no events or the clock is paused.
+    fn wait(&self) -> Option<Duration> {
+        if self.paused.is_some() {
+            return None;
+        }
+        let e = self.heap.peek()?;
+        Some(self.time(e.tick).saturating_sub(self.clock.now()))
+    }
+
+    fn receive(&mut self, input: Input) {
+        match input {
+            Input::Event(e) => self.push(e),
+            Input::Control(c) => self.control(c),
+        }
+    }
+
+    /// Adds the event to the heap, counting its delay from now. Tempo
+    /// changes are written into the tempo map straight away, so the events
+    /// after them are rescaled.
+    fn push(&mut self, rel: Event) {
+        let tick = self.position() + rel.del;
+        if let Message::Tempo(tempo) = rel.msg {
+            self.tempo.set_tempo(tick, tempo);
+        }
+        self.heap.push(EventAbs {
+            msg: rel.msg,
+            tick,
+            order: self.received,
+        });
+        self.received += 1;
+    }
+
+    fn control(&mut self, control: Control) {
+        match control {
+            Control::Pause => {
+                if self.paused.is_none() {
+                    self.paused = Some(self.position());
+                    self.release();
+                }
+            }
+            Control::Resume => {
+                if let Some(tick) = self.paused.take() {
+                    self.anchor = (self.clock.now(), tick);
+                }
+            }
+            Control::Clear => {
+                self.forget();
+                self.release();
+            }
+            Control::Panic => {
+                self.forget();
+                self.sounding.clear();
+                self.send(Message::Stop);
+            }
+            Control::Seek(tick) => {
+                self.heap.retain(|e| e.tick >= tick);
+                self.release();
+                match self.paused {
+                    Some(_) => self.paused = Some(tick),
+                    None => self.anchor = (self.clock.now(), tick),
+                }
+            }
+        }
+    }
+
+    /// Drops the events which are waiting, along with the tempo changes they
+    /// were going to make.
+    fn forget(&mut self) {
+        let now = self.position();
+        self.heap.clear();
+        self.tempo.changes.retain(|(tick, _)| *tick <= now);
+    }
+
+    /// Turns off the notes which are sounding.
+    fn release(&mut self) {
+        for note in std::mem::take(&mut self.sounding) {
+            self.send(Message::NoteOff(note));
+        }
+    }
+
+    /// Sends the events which are due.
+    fn send_due(&mut self) {
+        while self.wait() == Some(Duration::from_secs(0)) {
+            let msg = self.heap.pop().unwrap().msg;
+            self.send(msg);
+        }
+    }
+
+    fn send(&mut self, msg: Message) {
+        match msg {
+            Message::NoteOn(note) => self.sounding.push(note),
+            Message::NoteOff(note) => {
+                if let Some(i) = self.sounding.iter().position(|n| *n == note) {
+                    self.sounding.remove(i);
+                }
+            }
+            Message::Stop => self.sounding.clear(),
+            Message::Tempo(_) => {}
+        }
+        self.sent.push(msg);
+    }
+}
+
+/// Starts the sequencer at the given tempo, keeping real time. Events sent
+/// to it come back out of the receiver as messages once they are due.
+pub fn start(tempo: Bpm) -> (Sequencer, Receiver<Message>) {
     let (msg_tx, msg_rx) = channel();
-    let (event_tx, event_rx) = channel();
+    let (input_tx, input_rx) = channel();
 
     std::thread::spawn(move || {
-        // Heap of events that are awaiting processing. The soonest event that must be processed
-        // sits at the top of the heap
-        let mut heap = BinaryHeap::<EventAbs>::new();
+        let mut schedule = Schedule::new(tempo, RealTime::new());
 
         loop {
-            // outer Maybe: Some(_) if there is an event, None if there are no events
-            // inner Maybe: Some(t) if the event is going to happen in the future, after t time. None if it is in the past.
-            let t_event = heap
-                .peek()
-                .map(|e| e.ins.checked_duration_since(Instant::now()));
-
-            match t_event {
-                // soonest event must trigger in the future
-                Some(Some(t)) => match event_rx.recv_timeout(t) {
-                    Ok(e) => heap.push(rel_to_abs(e)),
-                    Err(RecvTimeoutError::Timeout) => {}
+            // wait for the soonest event to become due, or for something to
+            // be sent in the meantime
+            let input = match schedule.wait() {
+                Some(t) => match input_rx.recv_timeout(t) {
+                    Ok(input) => Some(input),
+                    Err(RecvTimeoutError::Timeout) => None,
                     Err(RecvTimeoutError::Disconnected) => break,
                 },
-                // soonest event must trigger now
-                Some(None) => {}
-                // no events in heap
-                None => match event_rx.recv() {
-                    Ok(e) => heap.push(rel_to_abs(e)),
+                // no events in heap, or the clock is paused
+                None => match input_rx.recv() {
+                    Ok(input) => Some(input),
                     _ => break,
                 },
             };
 
             // put any other events onto the heap
-            for e in event_rx.try_iter() {
-                heap.push(rel_to_abs(e));
+            for input in input.into_iter().chain(input_rx.try_iter()) {
+                schedule.receive(input);
             }
 
-            // predicate which is true if the heap has events that must trigger, false if it is empty or
-            // if all the remaining events are in the future
-            while heap
-                .peek()
-                .map(|e| e.ins <= Instant::now())
-                .unwrap_or(false)
-            {
-                // send the events that must fire through the message receiver
-                msg_tx.send(heap.pop().unwrap().msg).unwrap();
+            schedule.send_due();
+            for msg in schedule.sent.drain(..) {
+                msg_tx.send(msg).unwrap();
             }
         }
     });
 
-    (event_tx, msg_rx)
+    let sequencer = Sequencer {
+        target: Target::Schedule(input_tx),
+    };
+    (sequencer, msg_rx)
+}
+
+/// A sequencer driven by a virtual clock, which only moves when it is
+/// advanced. Nothing happens in the background, so it runs as fast as it is
+/// driven and always sends the same messages at the same times.
+pub struct VirtualSequencer {
+    schedule: Schedule<VirtualClock>,
+    inputs: Receiver<Input>,
+}
+
+impl VirtualSequencer {
+    /// Creates a sequencer at the given tempo, with its clock at zero, along
+    /// with the handle through which events are sent to it. Events are
+    /// received when the clock is next advanced, and counted from the time
+    /// it had reached.
+    pub fn new(tempo: Bpm) -> (Sequencer, VirtualSequencer) {
+        let (input_tx, inputs) = channel();
+        let sequencer = Sequencer {
+            target: Target::Schedule(input_tx),
+        };
+        let schedule = Schedule::new(tempo, VirtualClock::default());
+        (sequencer, VirtualSequencer { schedule, inputs })
+    }
+
+    /// Returns the time the clock has reached.
+    pub fn now(&self) -> Duration {
+        self.schedule.clock.now()
+    }
+
+    /// Moves the clock forward by the given time. Returns the messages which
+    /// became due on the way, along with the time at which each was due,
+    /// soonest first.
+    pub fn advance(&mut self, time: Duration) -> Vec<(Duration, Message)> {
+        let end = self.now() + time;
+        self.run(Some(end))
+    }
+
+    /// Moves the clock forward until every event has been sent, and returns
+    /// the messages as `advance` does. If the clock is paused, only the
+    /// messages sent so far are returned.
+    pub fn finish(&mut self) -> Vec<(Duration, Message)> {
+        self.run(None)
+    }
+
+    fn run(&mut self, end: Option<Duration>) -> Vec<(Duration, Message)> {
+        let schedule = &mut self.schedule;
+        for input in self.inputs.try_iter() {
+            schedule.receive(input);
+        }
+
+        let mut messages = vec![];
+        loop {
+            schedule.send_due();
+            let now = schedule.clock.now();
+            messages.extend(schedule.sent.drain(..).map(|msg| (now, msg)));
+
+            match schedule.wait() {
+                Some(t) if end.is_none_or(|end| now + t <= end) => {
+                    schedule.clock.set(now + t);
+                }
+                _ => break,
+            }
+        }
+        if let Some(end) = end {
+            schedule.clock.set(end);
+        }
+        messages
+    }
 }
 
 #[cfg(test)]
@@ -108,13 +587,73 @@ mod tests {
         use pitch_calc::Letter::C;
         let first_event = EventAbs {
             msg: Message::NoteOn(LetterOctave(C, 4)),
-            ins: Instant::now(),
+            tick: 0,
+            order: 1,
         };
         let second_event = EventAbs {
             msg: Message::Stop,
-            ins: Instant::now() + Duration::from_millis(200),
+            tick: PPQ,
+            order: 0,
+        };
+        let third_event = EventAbs {
+            msg: Message::Stop,
+            tick: PPQ,
+            order: 2,
         };
-        assert_eq!(first_event > second_event, true);
+        assert!(first_event > second_event);
+        assert!(second_event > third_event);
+    }
+
+    #[test]
+    fn tempo_map() {
+        let mut map = TempoMap::new(120);
+        assert_eq!(map.to_duration(PPQ), Duration::from_millis(500));
+        assert_eq!(map.to_ticks(Duration::from_secs(1)), 2 * PPQ);
+
+        // Twice as fast from the third beat onwards.
+        map.set_tempo(2 * PPQ, 240);
+        assert_eq!(map.tempo_at(2 * PPQ - 1), 120);
+        assert_eq!(map.tempo_at(2 * PPQ), 240);
+        assert_eq!(map.to_duration(4 * PPQ), Duration::from_millis(1500));
+        assert_eq!(map.to_ticks(Duration::from_millis(1500)), 4 * PPQ);
+        assert_eq!(map.to_ticks(Duration::from_millis(750)), PPQ * 3 / 2);
+        for tick in &[0, 1, PPQ, 3 * PPQ + 7, 10 * PPQ] {
+            assert_eq!(map.to_ticks(map.to_duration(*tick)), *tick);
+        }
+
+        // Changing the tempo at the same tick replaces the change.
+        map.set_tempo(2 * PPQ, 60);
+        assert_eq!(map.to_duration(3 * PPQ), Duration::from_secs(2));
+
+        // A tempo of 0 is slowed down to 1 beat per minute, rather than
+        // stopping time.
+        map.set_tempo(3 * PPQ, 0);
+        assert_eq!(map.tempo_at(3 * PPQ), 1);
+        assert_eq!(map.to_duration(4 * PPQ), Duration::from_secs(62));
+        assert_eq!(TempoMap::new(0), TempoMap::new(1));
+    }
+
+    #[test]
+    fn timelines() {
+        use pitch_calc::Letter::C;
+        let note = LetterOctave(C, 4);
+        let mut events = note_events(&[note], 0, PPQ);
+        events.push(Event {
+            msg: Message::Tempo(60),
+            del: PPQ / 2,
+        });
+        assert_eq!(
+            timeline(&events, 120),
+            vec![
+                (Duration::from_secs(0), Message::NoteOn(note)),
+                (Duration::from_millis(250), Message::Tempo(60)),
+                (Duration::from_millis(750), Message::NoteOff(note)),
+            ]
+        );
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
     }
 
     #[test]
@@ -124,25 +663,25 @@ mod tests {
         let first_msg = Message::NoteOn(LetterOctave(C, 4));
         let first_event = Event {
             msg: first_msg,
-            del: Duration::from_millis(0),
+            del: 0,
         };
 
         let second_msg = Message::Stop;
+        // 200ms at 120 beats per minute.
         let second_event = Event {
             msg: second_msg,
-            del: Duration::from_millis(200),
+            del: PPQ * 2 / 5,
         };
 
-        let (tx, rx) = start();
-
-        tx.send(first_event).unwrap();
-        tx.send(second_event).unwrap();
+        let (tx, mut sequencer) = VirtualSequencer::new(DEFAULT_TEMPO);
 
-        assert_eq!(rx.recv().unwrap(), first_msg);
-        assert!(rx.try_recv().is_err());
+        tx.send(first_event);
+        tx.send(second_event);
 
-        std::thread::sleep(Duration::from_millis(300));
-        assert_eq!(rx.try_recv().unwrap(), second_msg);
+        assert_eq!(sequencer.advance(ms(100)), vec![(ms(0), first_msg)]);
+        assert_eq!(sequencer.advance(ms(100)), vec![(ms(200), second_msg)]);
+        assert_eq!(sequencer.advance(ms(1000)), vec![]);
+        assert_eq!(sequencer.now(), ms(1200));
     }
 
     #[test]
@@ -153,37 +692,177 @@ mod tests {
         let msg_3 = Message::NoteOff(LetterOctave(A, 4));
         let msg_4 = Message::NoteOff(LetterOctave(B, 4));
 
-        let zero = Duration::from_millis(0);
-        let del = Duration::from_millis(200);
+        let zero = 0;
+        let del = PPQ * 2 / 5;
 
         let ev_1 = Event {
             msg: msg_1,
             del: zero,
         };
-        let ev_2 = Event {
-            msg: msg_3,
-            del: del,
-        };
+        let ev_2 = Event { msg: msg_3, del };
         let ev_3 = Event {
             msg: msg_2,
             del: zero,
         };
-        let ev_4 = Event {
-            msg: msg_4,
-            del: del,
-        };
+        let ev_4 = Event { msg: msg_4, del };
+
+        let (tx, mut sequencer) = VirtualSequencer::new(DEFAULT_TEMPO);
+        tx.send(ev_1);
+        tx.send(ev_2);
+        assert_eq!(sequencer.advance(ms(125)), vec![(ms(0), msg_1)]);
+
+        tx.send(ev_3);
+        tx.send(ev_4);
+        assert_eq!(
+            sequencer.finish(),
+            vec![(ms(125), msg_2), (ms(200), msg_3), (ms(325), msg_4)]
+        );
+    }
+
+    #[test]
+    fn tempo_changes() {
+        use pitch_calc::Letter::C;
+        let msg = Message::NoteOn(LetterOctave(C, 4));
+
+        // A beat at 120 beats per minute lasts 500ms, but doubling the tempo
+        // halfway through brings the note forward to 375ms.
+        let (tx, mut sequencer) = VirtualSequencer::new(120);
+        tx.send(Event { msg, del: PPQ });
+        assert_eq!(sequencer.advance(ms(250)), vec![]);
+        tx.send(Event {
+            msg: Message::Tempo(240),
+            del: 0,
+        });
+        assert_eq!(
+            sequencer.finish(),
+            vec![(ms(250), Message::Tempo(240)), (ms(375), msg)]
+        );
+
+        let (tx, mut sequencer) = VirtualSequencer::new(0);
+        tx.send(Event { msg, del: PPQ / 60 });
+        tx.send(Event {
+            msg: Message::Tempo(0),
+            del: 0,
+        });
+        assert_eq!(
+            sequencer.finish(),
+            vec![(ms(0), Message::Tempo(0)), (ms(1000), msg)]
+        );
+    }
+
+    #[test]
+    fn real_time() {
+        use pitch_calc::Letter::C;
+        let msg = Message::NoteOn(LetterOctave(C, 4));
+
+        // 50ms at 240 beats per minute.
+        let (tx, rx) = start(240);
+        let sent = Instant::now();
+        tx.send(Event { msg, del: PPQ / 5 });
+
+        assert_eq!(rx.recv().unwrap(), msg);
+        let waited = sent.elapsed();
+        assert!(waited >= ms(45), "{:?}", waited);
+    }
+
+    #[test]
+    fn transport_pause() {
+        use pitch_calc::Letter::{C, E, G};
+        let (tx, mut sequencer) = VirtualSequencer::new(DEFAULT_TEMPO);
+        let (c, e, g) = (LetterOctave(C, 4), LetterOctave(E, 4), LetterOctave(G, 4));
+        tx.send(Event {
+            msg: Message::NoteOn(c),
+            del: 0,
+        });
+        // 50ms at 120 beats per minute.
+        tx.send(Event {
+            msg: Message::NoteOn(e),
+            del: PPQ / 10,
+        });
+        assert_eq!(sequencer.advance(ms(25)), vec![(ms(0), Message::NoteOn(c))]);
+
+        // Pausing releases the note, and holds back the next one until the
+        // sequencer is resumed. Notes sent while paused wait as well, and
+        // are played as soon as it resumes.
+        tx.pause();
+        tx.send(Event {
+            msg: Message::NoteOn(g),
+            del: 0,
+        });
+        assert_eq!(
+            sequencer.advance(ms(100)),
+            vec![(ms(25), Message::NoteOff(c))]
+        );
+        tx.resume();
+        assert_eq!(
+            sequencer.finish(),
+            vec![(ms(125), Message::NoteOn(g)), (ms(150), Message::NoteOn(e))]
+        );
+    }
+
+    #[test]
+    fn transport_clear() {
+        use pitch_calc::Letter::C;
+        let note = LetterOctave(C, 4);
 
-        let (tx, rx) = start();
-        tx.send(ev_1).unwrap();
-        tx.send(ev_2).unwrap();
+        let (tx, mut sequencer) = VirtualSequencer::new(DEFAULT_TEMPO);
+        for event in note_events(&[note], 0, PPQ) {
+            tx.send(event);
+        }
+        assert_eq!(
+            sequencer.advance(ms(25)),
+            vec![(ms(0), Message::NoteOn(note))]
+        );
+        tx.clear();
+        assert_eq!(sequencer.finish(), vec![(ms(25), Message::NoteOff(note))]);
+
+        for event in note_events(&[note], 0, PPQ) {
+            tx.send(event);
+        }
+        assert_eq!(
+            sequencer.advance(ms(25)),
+            vec![(ms(25), Message::NoteOn(note))]
+        );
+        tx.panic();
+        assert_eq!(sequencer.finish(), vec![(ms(50), Message::Stop)]);
+    }
+
+    #[test]
+    fn transport_seek() {
+        use pitch_calc::Letter::{C, D};
+        let (c, d) = (LetterOctave(C, 4), LetterOctave(D, 4));
 
-        std::thread::sleep(Duration::from_millis(10));
-        tx.send(ev_3).unwrap();
-        tx.send(ev_4).unwrap();
+        // Five and ten seconds away at 120 beats per minute.
+        let (tx, mut sequencer) = VirtualSequencer::new(DEFAULT_TEMPO);
+        tx.send(Event {
+            msg: Message::NoteOn(c),
+            del: 10 * PPQ,
+        });
+        tx.send(Event {
+            msg: Message::NoteOn(d),
+            del: 20 * PPQ,
+        });
 
-        assert_eq!(rx.recv().unwrap(), msg_1);
-        assert_eq!(rx.recv().unwrap(), msg_2);
-        assert_eq!(rx.recv().unwrap(), msg_3);
-        assert_eq!(rx.recv().unwrap(), msg_4);
+        tx.seek(10 * PPQ);
+        assert_eq!(
+            sequencer.advance(ms(100)),
+            vec![(ms(0), Message::NoteOn(c))]
+        );
+
+        // Seeking past the second note skips it, and releases the first.
+        tx.seek(30 * PPQ);
+        assert_eq!(sequencer.finish(), vec![(ms(100), Message::NoteOff(c))]);
+    }
+
+    #[test]
+    fn recorder() {
+        let (tx, rx) = Sequencer::recorder();
+        let event = Event {
+            msg: Message::Stop,
+            del: PPQ,
+        };
+        tx.pause();
+        tx.send(event);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![event]);
     }
 }
@@ -0,0 +1,426 @@
+use crate::analysis::{degree_of, is_dominant, is_leading_tone};
+use crate::music_theory::*;
+use std::fmt;
+
+/// The ways a chord can be replaced by others.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum SubstitutionKind {
+    /// A dominant seventh a tritone away, which shares its third and seventh.
+    Tritone,
+    /// The relative minor of a major chord, or the relative major of a minor
+    /// one.
+    Relative,
+    /// The diatonic chord a third above or below.
+    DiatonicThird,
+    /// The dominant seventh on the flattened seventh degree, in place of V.
+    Backdoor,
+    /// The chord's own dominant, played before it or in its place.
+    SecondaryDominant,
+    /// The chord preceded by its own ii–V.
+    RelatedTwoFive,
+    /// A diminished seventh a half step below the chord, leading into it.
+    DiminishedPassing,
+    /// The chord on the same degree of the parallel major or minor key.
+    ModalInterchange,
+}
+
+impl SubstitutionKind {
+    /// How strongly a substitution of this kind is suggested, before
+    /// counting the notes it shares with the chord it replaces.
+    fn weight(self) -> i32 {
+        match self {
+            SubstitutionKind::Tritone => 7,
+            SubstitutionKind::RelatedTwoFive => 6,
+            SubstitutionKind::Backdoor => 5,
+            SubstitutionKind::SecondaryDominant => 4,
+            SubstitutionKind::Relative => 3,
+            SubstitutionKind::DiatonicThird => 3,
+            SubstitutionKind::ModalInterchange => 2,
+            SubstitutionKind::DiminishedPassing => 2,
+        }
+    }
+}
+
+impl fmt::Display for SubstitutionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubstitutionKind::Tritone => write!(f, "tritone substitution"),
+            SubstitutionKind::Relative => write!(f, "relative major/minor"),
+            SubstitutionKind::DiatonicThird => write!(f, "diatonic third substitution"),
+            SubstitutionKind::Backdoor => write!(f, "backdoor dominant"),
+            SubstitutionKind::SecondaryDominant => write!(f, "secondary dominant"),
+            SubstitutionKind::RelatedTwoFive => write!(f, "related ii-V"),
+            SubstitutionKind::DiminishedPassing => write!(f, "diminished passing chord"),
+            SubstitutionKind::ModalInterchange => write!(f, "modal interchange"),
+        }
+    }
+}
+
+/// One or more chords which can be played in place of another, along with
+/// the reason why they work.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Substitution {
+    pub kind: SubstitutionKind,
+    pub chords: Vec<Chord>,
+    pub rationale: String,
+}
+
+/// Returns the notes of the chord in root position, without their octaves.
+fn spelled(chord: &Chord) -> Vec<SpelledNote> {
+    chord
+        .with_bass(None)
+        .spelled_notes()
+        .into_iter()
+        .map(|(n, _)| n)
+        .collect()
+}
+
+/// Returns the notes of `a` which are also in `b`.
+fn shared(a: &Chord, b: &Chord) -> Vec<SpelledNote> {
+    let b: Vec<i32> = spelled(b)
+        .iter()
+        .map(|n| n.semitones().rem_euclid(12))
+        .collect();
+    spelled(a)
+        .into_iter()
+        .filter(|n| b.contains(&n.semitones().rem_euclid(12)))
+        .collect()
+}
+
+/// Writes a list of notes as "B and F" or "E, G and B".
+fn note_list(notes: &[SpelledNote]) -> String {
+    let names: Vec<String> = notes.iter().map(|n| n.to_string()).collect();
+    match names.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        None => "no notes".to_owned(),
+    }
+}
+
+fn has_seventh(chord: &Chord) -> bool {
+    chord.quality.iter().any(|i| i.number == degrees::VII)
+}
+
+/// Returns the chord built on `root`, with the same octave as `chord`.
+fn built_on(chord: &Chord, root: SpelledNote, quality: Quality) -> Chord {
+    chord
+        .with_bass(None)
+        .with_root_letter(root.letter())
+        .with_quality(quality)
+}
+
+/// The dominant seventh, or the major triad if `seventh` is false.
+fn dominant_quality(seventh: bool) -> Quality {
+    use intervals::*;
+    if seventh {
+        vec![Maj3rd, Per5th, Min7th]
+    } else {
+        vec![Maj3rd, Per5th]
+    }
+}
+
+/// Returns the chord of the key on the same degree as the root of `chord`,
+/// with as many notes, if the root is in the key.
+fn diatonic_on(key: &Key, chord: &Chord, degree: Degree) -> Chord {
+    let size = if has_seventh(chord) {
+        ChordSize::Seventh
+    } else {
+        ChordSize::Triad
+    };
+    let diatonic = &key.diatonic_chords(size)[(degree - 1).rem_euclid(7) as usize];
+    built_on(
+        chord,
+        SpelledNote::from_letter(diatonic.root_letter()),
+        diatonic.quality.clone(),
+    )
+}
+
+/// Returns the chord's own dominant seventh, ii–V and passing diminished
+/// chord, all of which lead into it.
+fn approaches(chord: &Chord) -> Vec<(SubstitutionKind, Vec<Chord>, String)> {
+    use intervals::*;
+    let root = spelled(chord)[0];
+    let minor = chord.quality.contains(&Min3rd);
+    let mut found = vec![];
+
+    let five = root.above(Per5th);
+    let dominant = built_on(chord, five, dominant_quality(true));
+    found.push((
+        SubstitutionKind::SecondaryDominant,
+        vec![dominant.clone(), chord.clone()],
+        format!(
+            "its own dominant on {}, whose leading tone {} rises to the root",
+            five,
+            five.above(Maj3rd)
+        ),
+    ));
+
+    let two = root.above(Maj2nd);
+    let two_quality = if minor {
+        vec![Min3rd, Dim5th, Min7th]
+    } else {
+        vec![Min3rd, Per5th, Min7th]
+    };
+    found.push((
+        SubstitutionKind::RelatedTwoFive,
+        vec![built_on(chord, two, two_quality), dominant, chord.clone()],
+        format!(
+            "a ii-V from {} through {} sets up the chord as a temporary tonic",
+            two, five
+        ),
+    ));
+
+    let below = root.above(Maj7th);
+    found.push((
+        SubstitutionKind::DiminishedPassing,
+        vec![
+            built_on(chord, below, vec![Min3rd, Dim5th, Dim7th]),
+            chord.clone(),
+        ],
+        format!(
+            "a diminished seventh on {} leads into the root by half step",
+            below
+        ),
+    ));
+    found
+}
+
+/// Lists the chords which can be played in place of `chord` in the key, with
+/// the most strongly suggested first. Substitutions which share more notes
+/// with the chord they replace are preferred.
+pub fn substitutions(key: &Key, chord: &Chord) -> Vec<Substitution> {
+    use intervals::*;
+    let notes = spelled(chord);
+    let root = notes[0];
+    let (degree, accidental) = degree_of(key, root);
+    let seventh = has_seventh(chord);
+    let dominant = is_dominant(&chord.quality) && chord.quality.contains(&Min7th);
+    let mut found: Vec<(SubstitutionKind, Vec<Chord>, String)> = vec![];
+
+    if dominant {
+        let tritone = root.above(Dim5th);
+        let sub = built_on(chord, tritone, chord.quality.clone());
+        found.push((
+            SubstitutionKind::Tritone,
+            vec![sub.clone()],
+            format!(
+                "shares the tritone {}, and its root falls by half step where {} falls by a fifth",
+                note_list(&shared(chord, &sub)),
+                root
+            ),
+        ));
+
+        let resolution = root.above(Per4th);
+        let backdoor = resolution.above(Min7th);
+        if pitch_class(resolution.letter()) == pitch_class(key.tonic()) {
+            found.push((
+                SubstitutionKind::Backdoor,
+                vec![built_on(chord, backdoor, dominant_quality(true))],
+                format!(
+                    "resolves to {} from a whole step below, borrowing notes from the parallel minor",
+                    resolution
+                ),
+            ));
+        }
+
+        let two = root.above(Per5th);
+        found.push((
+            SubstitutionKind::RelatedTwoFive,
+            vec![
+                built_on(chord, two, vec![Min3rd, Per5th, Min7th]),
+                chord.clone(),
+            ],
+            format!("its related ii on {} prepares it a fifth above", two),
+        ));
+    }
+
+    let major = chord.quality.contains(&Maj3rd) && chord.quality.contains(&Per5th) && !dominant;
+    let minor = chord.quality.contains(&Min3rd) && chord.quality.contains(&Per5th);
+    if major {
+        let relative = root.above(Maj6th);
+        let quality = if seventh {
+            vec![Min3rd, Per5th, Min7th]
+        } else {
+            vec![Min3rd, Per5th]
+        };
+        let sub = built_on(chord, relative, quality);
+        found.push((
+            SubstitutionKind::Relative,
+            vec![sub.clone()],
+            format!(
+                "its relative minor shares {}",
+                note_list(&shared(chord, &sub))
+            ),
+        ));
+    } else if minor {
+        let relative = root.above(Min3rd);
+        let quality = if seventh {
+            vec![Maj3rd, Per5th, Maj7th]
+        } else {
+            vec![Maj3rd, Per5th]
+        };
+        let sub = built_on(chord, relative, quality);
+        found.push((
+            SubstitutionKind::Relative,
+            vec![sub.clone()],
+            format!(
+                "its relative major shares {}",
+                note_list(&shared(chord, &sub))
+            ),
+        ));
+    }
+
+    if accidental == 0 {
+        for (step, direction) in &[(2, "above"), (-2, "below")] {
+            let sub = diatonic_on(key, chord, degree + step);
+            found.push((
+                SubstitutionKind::DiatonicThird,
+                vec![sub.clone()],
+                format!(
+                    "the diatonic chord a third {} shares {}",
+                    direction,
+                    note_list(&shared(chord, &sub))
+                ),
+            ));
+        }
+
+        let parallel = if key.is_minor() {
+            Key::major(key.tonic())
+        } else {
+            Key::minor(key.tonic())
+        };
+        let sub = diatonic_on(&parallel, chord, degree);
+        let mode = if key.is_minor() { "major" } else { "minor" };
+        found.push((
+            SubstitutionKind::ModalInterchange,
+            vec![sub],
+            format!("borrowed from the parallel {} key on the same degree", mode),
+        ));
+    }
+
+    // Minor chords on a degree whose chord can be tonicised become that
+    // degree's secondary dominant, e.g. ii becomes V/V.
+    if minor && accidental == 0 {
+        let target = root.above(Per4th);
+        let (target_degree, target_accidental) = degree_of(key, target);
+        let target_chord = &key.diatonic_chords(ChordSize::Triad)[target_degree as usize - 1];
+        if target_accidental == 0 && !is_leading_tone(&target_chord.quality) {
+            found.push((
+                SubstitutionKind::SecondaryDominant,
+                vec![built_on(chord, root, dominant_quality(seventh))],
+                format!(
+                    "with a major third it becomes the dominant of {}, raising {} to {}",
+                    target,
+                    root.above(Min3rd),
+                    root.above(Maj3rd)
+                ),
+            ));
+        }
+    }
+
+    if !dominant && !is_leading_tone(&chord.quality) {
+        found.extend(approaches(chord));
+    } else if dominant {
+        // The related ii of a dominant was added above, in place of a
+        // whole ii-V.
+        found.extend(
+            approaches(chord)
+                .into_iter()
+                .filter(|(kind, _, _)| *kind != SubstitutionKind::RelatedTwoFive),
+        );
+    }
+
+    // Leave out substitutions which are the chord itself, or which have
+    // already been found.
+    let mut substitutions: Vec<Substitution> = vec![];
+    for (kind, chords, rationale) in found {
+        let same = chords.len() == 1 && chords[0].notes() == chord.with_bass(None).notes();
+        if same || substitutions.iter().any(|s| s.chords == chords) {
+            continue;
+        }
+        substitutions.push(Substitution {
+            kind,
+            chords,
+            rationale,
+        });
+    }
+
+    let score = |s: &Substitution| {
+        let common = s
+            .chords
+            .iter()
+            .filter(|c| *c != chord)
+            .map(|c| shared(chord, c).len())
+            .max();
+        s.kind.weight() + common.unwrap_or(0) as i32
+    };
+    substitutions.sort_by_key(|s| -score(s));
+    substitutions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music_theory::tests::chord;
+    use intervals::*;
+    use Letter::*;
+
+    fn roots(substitution: &Substitution) -> Vec<String> {
+        substitution
+            .chords
+            .iter()
+            .map(|c| SpelledNote::from_letter(c.root_letter()).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn dominant_substitutions() {
+        use SubstitutionKind::*;
+        let g7 = chord(G, vec![Maj3rd, Per5th, Min7th]);
+        let subs = substitutions(&Key::major(C), &g7);
+        let find = |kind| subs.iter().find(|s| s.kind == kind).unwrap();
+
+        assert_eq!(subs[0].kind, Tritone);
+        assert_eq!(roots(&subs[0]), vec!["Db"]);
+        assert_eq!(subs[0].chords[0].quality, g7.quality);
+        assert!(subs[0].rationale.contains("B and F"));
+
+        assert_eq!(roots(find(Backdoor)), vec!["Bb"]);
+        assert_eq!(roots(find(RelatedTwoFive)), vec!["D", "G"]);
+        assert_eq!(roots(find(SecondaryDominant)), vec!["D", "G"]);
+        assert_eq!(roots(find(DiminishedPassing)), vec!["F#", "G"]);
+        assert_eq!(
+            find(ModalInterchange).chords[0].quality,
+            vec![Min3rd, Per5th, Min7th]
+        );
+        let thirds: Vec<Vec<String>> = subs
+            .iter()
+            .filter(|s| s.kind == DiatonicThird)
+            .map(roots)
+            .collect();
+        assert_eq!(thirds, vec![vec!["B"], vec!["E"]]);
+        assert!(subs.iter().all(|s| s.kind != Relative));
+    }
+
+    #[test]
+    fn minor_and_major_substitutions() {
+        use SubstitutionKind::*;
+        let dm7 = chord(D, vec![Min3rd, Per5th, Min7th]);
+        let subs = substitutions(&Key::major(C), &dm7);
+        let find = |kind| subs.iter().find(|s| s.kind == kind).unwrap();
+        assert_eq!(roots(find(Relative)), vec!["F"]);
+        assert!(subs.iter().any(|s| s.kind == SecondaryDominant
+            && s.chords == vec![chord(D, vec![Maj3rd, Per5th, Min7th])]));
+        assert!(subs.iter().all(|s| s.kind != Tritone && s.kind != Backdoor));
+
+        let f = chord(F, vec![Maj3rd, Per5th]);
+        let subs = substitutions(&Key::major(C), &f);
+        let find = |kind| subs.iter().find(|s| s.kind == kind).unwrap();
+        assert_eq!(roots(find(Relative)), vec!["D"]);
+        assert_eq!(
+            find(ModalInterchange).chords[0],
+            chord(F, vec![Min3rd, Per5th])
+        );
+        assert_eq!(roots(find(RelatedTwoFive)), vec!["G", "C", "F"]);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::database::*;
+use crate::music_theory::*;
+use diesel::sqlite::SqliteConnection;
+
+/// Writes down a note in words, e.g. "B-flat" or "F-double-sharp".
+fn long_note_name(note: SpelledNote) -> String {
+    let accidental = match note.accidental {
+        -2 => "-double-flat",
+        -1 => "-flat",
+        1 => "-sharp",
+        2 => "-double-sharp",
+        _ => "",
+    };
+    format!("{:?}{}", note.natural, accidental)
+}
+
+/// Writes down a number in words, for the degrees used in chord symbols.
+fn long_number(degree: Degree) -> String {
+    match degree {
+        2 => "two".to_owned(),
+        4 => "four".to_owned(),
+        5 => "five".to_owned(),
+        6 => "six".to_owned(),
+        7 => "seven".to_owned(),
+        9 => "nine".to_owned(),
+        11 => "eleven".to_owned(),
+        13 => "thirteen".to_owned(),
+        _ => degree.to_string(),
+    }
+}
+
+/// Names a quality which is not in the database as one that is, followed by
+/// the notes it lacks, such as "7b9" followed by "#11". Only extensions
+/// (ninths and above) may be added this way, and only unaltered, flattened
+/// or sharpened. Returns `None` if no chord in the database fits.
+fn compose(quality: &[Interval], style: ChordSymbols, conn: &SqliteConnection) -> Option<String> {
+    let mut wanted = quality.to_vec();
+    wanted.sort_unstable();
+
+    // Prefer the largest chord, then the one which leaves out the highest
+    // extensions.
+    let (base, extensions) = get_all_chords(conn)?
+        .into_iter()
+        .filter(|(_, q)| q.iter().all(|n| wanted.contains(n)))
+        .map(|(_, q)| {
+            let rest: Quality = wanted.iter().filter(|n| !q.contains(n)).copied().collect();
+            (q, rest)
+        })
+        .filter(|(_, rest)| rest.iter().all(|i| i.number >= degrees::IX))
+        .max_by_key(|(q, rest)| (q.len(), rest.iter().map(|i| i.number).min()))?;
+
+    let base_name = get_styled_name(&base, style, conn)?;
+    let mut words = vec![];
+    let has_seventh = base
+        .iter()
+        .any(|i| i.number == degrees::VI || i.number == degrees::VII);
+    for interval in &extensions {
+        let degree = interval.number;
+        let accidental = match interval.semitones() - natural_interval(degree)?.semitones() {
+            -1 => "b",
+            0 if !has_seventh => "add",
+            0 => "",
+            1 => "#",
+            _ => return None,
+        };
+        words.push((accidental, degree));
+    }
+
+    if style == ChordSymbols::Long {
+        let mut name = vec![base_name];
+        for (accidental, degree) in words {
+            let accidental = match accidental {
+                "b" => "flat ",
+                "#" => "sharp ",
+                "add" => "add ",
+                _ => "",
+            };
+            name.push(format!("{}{}", accidental, long_number(degree)));
+        }
+        Some(name.join(" "))
+    } else {
+        let extensions: String = words
+            .iter()
+            .map(|(accidental, degree)| format!("{}{}", accidental, degree))
+            .collect();
+        Some(base_name + &extensions)
+    }
+}
+
+impl Chord {
+    /// Returns the name of the chord, written in the given `style`, such as
+    /// "Bbm7b5", "Bbø7" or "B-flat minor seven flat five". The quality is
+    /// written with the name preferred in the style's chord symbols, and
+    /// chords which are not in the database are named after the closest one
+    /// that is, plus the extensions they add. Chords that cannot be named at
+    /// all are written as their root followed by a question mark.
+    pub fn symbol(&self, style: &NotationStyle, conn: &SqliteConnection) -> String {
+        let symbols = style.symbols;
+        let note = |letter: Letter| {
+            let note = SpelledNote::from_letter(letter);
+            match symbols {
+                ChordSymbols::Long => long_note_name(note),
+                _ => style.note_to_string(note),
+            }
+        };
+
+        let quality = get_styled_name(&self.quality, symbols, conn)
+            .or_else(|| compose(&self.quality, symbols, conn))
+            .unwrap_or_else(|| "?".to_owned());
+        let bass = match (self.bass, symbols) {
+            (Some(bass), ChordSymbols::Long) => format!(" over {}", note(bass)),
+            (Some(bass), _) => format!("/{}", note(bass)),
+            (None, _) => "".to_owned(),
+        };
+
+        match symbols {
+            ChordSymbols::Long => format!("{} {}{}", note(self.root_letter()), quality, bass),
+            _ => format!("{}{}{}", note(self.root_letter()), quality, bass),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chord_library;
+    use crate::parser::chord_symbol;
+
+    fn symbol(input: &str, symbols: ChordSymbols) -> String {
+        let conn = initialise_database().unwrap();
+        chord_library::populate_database(&conn);
+        let style = NotationStyle {
+            symbols,
+            ..NotationStyle::default()
+        };
+        match chord_symbol(input) {
+            Ok(("", s)) => s.to_chord(4, &conn).unwrap().symbol(&style, &conn),
+            _ => panic!("Could not parse {}", input),
+        }
+    }
+
+    #[test]
+    fn symbol_styles() {
+        use ChordSymbols::*;
+        assert_eq!(symbol("Bbm7b5", Pop), "Bbm7b5");
+        assert_eq!(symbol("Bbm7b5", Jazz), "Bbø7");
+        assert_eq!(symbol("Bbm7b5", Long), "B-flat minor seven flat five");
+
+        assert_eq!(symbol("C major seventh", Pop), "Cmaj7");
+        assert_eq!(symbol("Cmaj7", Jazz), "CΔ7");
+        assert_eq!(symbol("D-7", Pop), "Dm7");
+        assert_eq!(symbol("Dm7", Jazz), "D-7");
+        assert_eq!(symbol("G7", Jazz), "G7");
+        assert_eq!(symbol("F#m/C#", Pop), "F#m/C#");
+        assert_eq!(symbol("F#m/C#", Long), "F-sharp minor over C-sharp");
+    }
+
+    #[test]
+    fn composed_symbols() {
+        use ChordSymbols::*;
+        assert_eq!(symbol("C7b9#11", Pop), "C7b9#11");
+        assert_eq!(symbol("C7(#11, b9)", Pop), "C7b9#11");
+        assert_eq!(symbol("Fmaj9#11", Jazz), "FΔ9#11");
+        assert_eq!(symbol("Gsus4add9", Pop), "Gsus4add9");
+        assert_eq!(symbol("C7b9#11", Long), "C seven flat nine sharp eleven");
+        assert_eq!(symbol("C7no5", Pop), "C?");
+    }
+}
@@ -0,0 +1,297 @@
+use crate::music_theory::*;
+use crate::voicing::{above, height, pitch_height, Voice};
+
+/// The range of notes a single voice may sing.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct VoiceRange {
+    pub name: &'static str,
+    pub low: LetterOctave,
+    pub high: LetterOctave,
+}
+
+/// Four-part choir, from the lowest voice upwards.
+pub const SATB: [VoiceRange; 4] = [
+    VoiceRange {
+        name: "bass",
+        low: LetterOctave(Letter::E, 2),
+        high: LetterOctave(Letter::C, 4),
+    },
+    VoiceRange {
+        name: "tenor",
+        low: LetterOctave(Letter::C, 3),
+        high: LetterOctave(Letter::A, 4),
+    },
+    VoiceRange {
+        name: "alto",
+        low: LetterOctave(Letter::G, 3),
+        high: LetterOctave(Letter::D, 5),
+    },
+    VoiceRange {
+        name: "soprano",
+        low: LetterOctave(Letter::C, 4),
+        high: LetterOctave(Letter::G, 5),
+    },
+];
+
+/// A progression of chords voiced for a fixed number of voices.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct VoiceLeading {
+    /// The notes of every chord, one per voice, from the lowest voice
+    /// upwards.
+    pub chords: Vec<Vec<(SpelledNote, Octave)>>,
+}
+
+impl VoiceLeading {
+    /// Returns the notes sung by one voice, from the first chord to the last.
+    pub fn voice(&self, voice: usize) -> Vec<(SpelledNote, Octave)> {
+        self.chords.iter().map(|c| c[voice]).collect()
+    }
+
+    /// Returns, for every voice, the number of semitones it moves by between
+    /// each pair of chords. Positive numbers go up.
+    pub fn movements(&self) -> Vec<Vec<Semitones>> {
+        let voices = self.chords.first().map(|c| c.len()).unwrap_or(0);
+        (0..voices)
+            .map(|v| {
+                self.chords
+                    .windows(2)
+                    .map(|w| height(w[1][v]) - height(w[0][v]))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns the number of semitones moved by all the voices together.
+    pub fn total_movement(&self) -> Semitones {
+        self.movements().iter().flatten().map(|m| m.abs()).sum()
+    }
+
+    /// Returns the notes of every chord as pitches, ready to be played.
+    pub fn notes(&self) -> Vec<Vec<LetterOctave>> {
+        self.chords
+            .iter()
+            .map(|c| c.iter().map(|(n, o)| n.letter_octave(*o)).collect())
+            .collect()
+    }
+}
+
+/// Returns the semitones moved by the voices going from `a` to `b`.
+fn distance(a: &[Voice], b: &[Voice]) -> i32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (height(*a) - height(*b)).abs())
+        .sum()
+}
+
+/// Returns every way of voicing the chord with one note per voice, within
+/// the voices' ranges and without voices crossing or sharing a note. The
+/// lowest voice plays the bass of the chord. If there are more notes than
+/// voices, the perfect fifth is left out first, then the highest notes.
+fn candidates(chord: &Chord, ranges: &[VoiceRange]) -> Vec<Vec<Voice>> {
+    let spelled: Vec<SpelledNote> = chord.spelled_notes().iter().map(|(n, _)| *n).collect();
+    let bass = pitch_class_of(spelled[0]);
+
+    // Chord tones other than the bass, along with their interval above the
+    // root.
+    let tones: Vec<(Interval, SpelledNote)> = std::iter::once(intervals::Root)
+        .chain(chord.quality.iter().copied())
+        .zip(
+            chord
+                .with_bass(None)
+                .spelled_notes()
+                .into_iter()
+                .map(|(n, _)| n),
+        )
+        .filter(|(_, n)| pitch_class_of(*n) != bass)
+        .collect();
+    let mut required: Vec<SpelledNote> = tones
+        .iter()
+        .filter(|(i, _)| tones.len() < ranges.len() || *i != intervals::Per5th)
+        .map(|(_, n)| *n)
+        .collect();
+    required.truncate(ranges.len() - 1);
+
+    let mut found = vec![];
+    let mut current = vec![];
+    fill(&spelled, &required, ranges, &mut current, &mut found);
+    found
+}
+
+/// Returns the pitch class of a note, from 0 for C to 11 for B.
+fn pitch_class_of(note: SpelledNote) -> i32 {
+    note.semitones().rem_euclid(12)
+}
+
+/// Adds every possible note for the next voice in `current`, recursing until
+/// every voice has a note. Complete voicings which contain all of the
+/// `required` notes are added to `found`.
+fn fill(
+    spelled: &[SpelledNote],
+    required: &[SpelledNote],
+    ranges: &[VoiceRange],
+    current: &mut Vec<Voice>,
+    found: &mut Vec<Vec<Voice>>,
+) {
+    let missing = required
+        .iter()
+        .filter(|r| {
+            !current
+                .iter()
+                .any(|(n, _)| pitch_class_of(*n) == pitch_class_of(**r))
+        })
+        .count();
+    let voice = current.len();
+    if missing > ranges.len() - voice {
+        return;
+    }
+    if voice == ranges.len() {
+        found.push(current.clone());
+        return;
+    }
+
+    let range = ranges[voice];
+    let floor = current
+        .last()
+        .map(|v| height(*v))
+        .unwrap_or(i32::MIN)
+        .max(pitch_height(range.low) - 1);
+    // The lowest voice always plays the bass.
+    let notes = if voice == 0 { &spelled[..1] } else { spelled };
+    for note in notes {
+        let mut candidate = above(*note, floor);
+        while height(candidate) <= pitch_height(range.high) {
+            current.push(candidate);
+            fill(spelled, required, ranges, current, found);
+            current.pop();
+            candidate.1 += 1;
+        }
+    }
+}
+
+/// Voices every chord with one note per voice in `ranges`, so that the
+/// voices move by as few semitones as possible in total. Notes shared by
+/// neighbouring chords are kept in the same voice where possible. The first
+/// chord is voiced as close to the middle of each voice's range as it can
+/// be. Returns `None` if a chord cannot be voiced within the ranges.
+pub fn lead(chords: &[Chord], ranges: &[VoiceRange]) -> Option<VoiceLeading> {
+    if chords.is_empty() || ranges.is_empty() {
+        return Some(VoiceLeading { chords: vec![] });
+    }
+    let options: Vec<Vec<Vec<Voice>>> = chords.iter().map(|c| candidates(c, ranges)).collect();
+    if options.iter().any(|o| o.is_empty()) {
+        return None;
+    }
+
+    // Lowest cost of reaching each voicing of the current chord, and the
+    // voicing of the previous chord it was reached from. Costs compare the
+    // total movement first, then how far the first chord is from the middle
+    // of the ranges.
+    let middle = |v: &Vec<Voice>| -> i32 {
+        v.iter()
+            .zip(ranges)
+            .map(|(n, r)| (2 * height(*n) - pitch_height(r.low) - pitch_height(r.high)).abs())
+            .sum()
+    };
+    let mut costs: Vec<(i32, i32)> = options[0].iter().map(|v| (0, middle(v))).collect();
+    let mut paths: Vec<Vec<usize>> = vec![];
+
+    for pair in options.windows(2) {
+        let (previous, next) = (&pair[0], &pair[1]);
+        let mut new_costs = vec![];
+        let mut from = vec![];
+        for voicing in next {
+            let (best, cost) = previous
+                .iter()
+                .zip(&costs)
+                .map(|(p, (moved, start))| (moved + distance(p, voicing), *start))
+                .enumerate()
+                .min_by_key(|(_, cost)| *cost)
+                .unwrap();
+            new_costs.push(cost);
+            from.push(best);
+        }
+        costs = new_costs;
+        paths.push(from);
+    }
+
+    // Follow the cheapest voicing of the last chord back to the start.
+    let mut index = (0..costs.len()).min_by_key(|i| costs[*i]).unwrap();
+    let mut voiced = vec![options[options.len() - 1][index].clone()];
+    for (i, from) in paths.iter().enumerate().rev() {
+        index = from[index];
+        voiced.push(options[i][index].clone());
+    }
+    voiced.reverse();
+
+    Some(VoiceLeading { chords: voiced })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music_theory::tests::chord;
+    use intervals::*;
+    use Letter::*;
+
+    fn path(leading: &VoiceLeading, voice: usize) -> String {
+        let style = NotationStyle::default();
+        leading
+            .voice(voice)
+            .iter()
+            .map(|(n, o)| style.note_octave_to_string(*n, *o))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn two_five_one() {
+        let chords = vec![
+            chord(D, vec![Min3rd, Per5th, Min7th]),
+            chord(G, vec![Maj3rd, Per5th, Min7th]),
+            chord(C, vec![Maj3rd, Per5th, Maj7th]),
+        ];
+        let leading = lead(&chords, &SATB).unwrap();
+
+        // The bass plays the roots, and the upper voices move by step at
+        // most, keeping the common tones.
+        assert_eq!(path(&leading, 0), "D3 G3 C4");
+        assert_eq!(path(&leading, 1), "C4 D4 E4");
+        assert_eq!(path(&leading, 2), "F4 F4 G4");
+        assert_eq!(path(&leading, 3), "A4 B4 B4");
+        for voice in 1..4 {
+            assert!(leading.movements()[voice].iter().all(|m| m.abs() <= 2));
+        }
+        assert!(leading
+            .chords
+            .iter()
+            .zip(&chords)
+            .all(|(voiced, chord)| voiced
+                .iter()
+                .all(|(n, _)| chord.notes().iter().any(|c| c.letter() == n.letter()))));
+        assert_eq!(
+            leading.movements(),
+            vec![vec![5, 5], vec![2, 2], vec![0, 2], vec![2, 0]]
+        );
+        assert_eq!(leading.total_movement(), 18);
+    }
+
+    #[test]
+    fn common_tones() {
+        // C and E are shared, so only one voice moves from C major to A minor.
+        let chords = vec![
+            chord(C, vec![Maj3rd, Per5th]),
+            chord(A, vec![Min3rd, Per5th]).with_bass(Some(C)),
+        ];
+        let leading = lead(&chords, &SATB[1..]).unwrap();
+        assert_eq!(leading.total_movement(), 2);
+        assert_eq!(leading.notes()[1][0].letter(), C);
+
+        // Nothing fits in a range of a single semitone.
+        let narrow = [VoiceRange {
+            name: "narrow",
+            low: LetterOctave(Db, 4),
+            high: LetterOctave(D, 4),
+        }];
+        assert_eq!(lead(&chords, &narrow), None);
+    }
+}
@@ -0,0 +1,337 @@
+use crate::music_theory::*;
+use std::fmt;
+
+/// A note along with the octave it is written in.
+pub(crate) type Voice = (SpelledNote, Octave);
+
+/// Lowest note of a spread voicing, unless another one is given.
+pub const SPREAD_LOW: LetterOctave = LetterOctave(Letter::C, 2);
+/// Highest note of a spread voicing, unless another one is given.
+pub const SPREAD_HIGH: LetterOctave = LetterOctave(Letter::C, 6);
+
+/// Ways of arranging the notes of a chord across the registers.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum Voicing {
+    /// Every note as close as possible to the one below it, starting from
+    /// the bass.
+    #[default]
+    Close,
+    /// Close voicing with the second note from the bottom raised an octave.
+    Open,
+    /// Close voicing with the second note from the top dropped an octave.
+    Drop2,
+    /// Close voicing with the third note from the top dropped an octave.
+    Drop3,
+    /// Close voicing with the second and fourth notes from the top dropped
+    /// an octave.
+    Drop24,
+    /// The root, third and seventh only.
+    Shell,
+    /// Third, fifth, seventh and ninth, without the root.
+    RootlessA,
+    /// Seventh, ninth, third and fifth, without the root.
+    RootlessB,
+    /// The bass as low as the range allows, and the other notes spaced out
+    /// evenly up to its top. The notes always keep their order, so a note
+    /// which cannot fit between the one below it and the top of the range is
+    /// left above it.
+    Spread(SpreadRange),
+}
+
+/// The range of notes a spread voicing is spread across, which spans at
+/// least an octave.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct SpreadRange {
+    low: LetterOctave,
+    high: LetterOctave,
+}
+
+impl SpreadRange {
+    /// Returns the range from `low` to `high`, or `None` unless `low` is at
+    /// least an octave below `high`, which every chord needs to fit.
+    pub fn new(low: LetterOctave, high: LetterOctave) -> Option<SpreadRange> {
+        if pitch_height(high) - pitch_height(low) >= 12 {
+            Some(SpreadRange { low, high })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the lowest note of the range.
+    pub fn low(&self) -> LetterOctave {
+        self.low
+    }
+
+    /// Returns the highest note of the range.
+    pub fn high(&self) -> LetterOctave {
+        self.high
+    }
+}
+
+impl Default for SpreadRange {
+    fn default() -> SpreadRange {
+        SpreadRange {
+            low: SPREAD_LOW,
+            high: SPREAD_HIGH,
+        }
+    }
+}
+
+/// Semitones above C0 of a written note, so that Cb5 and B4 are the same.
+pub(crate) fn height((note, octave): Voice) -> i32 {
+    octave * 12 + note.semitones()
+}
+
+/// Semitones above C0 of a pitch.
+pub(crate) fn pitch_height(note: LetterOctave) -> i32 {
+    note.octave() * 12 + pitch_class(note.letter())
+}
+
+/// Writes `note` in the octave in which it sounds lowest, while still
+/// being higher than `floor`.
+pub(crate) fn above(note: SpelledNote, floor: i32) -> Voice {
+    let height = floor + 1 + (note.semitones() - floor - 1).rem_euclid(12);
+    (note, (height - note.semitones()).div_euclid(12))
+}
+
+/// Moves every note in `positions`, counting down from the top, by
+/// `octaves`, and sorts the notes from the lowest upwards. Positions past
+/// the bottom of the chord are ignored.
+fn shifted(mut voices: Vec<Voice>, positions: &[usize], octaves: Octave) -> Vec<Voice> {
+    let len = voices.len();
+    for position in positions.iter().filter(|p| (1..=len).contains(*p)) {
+        voices[len - position].1 += octaves;
+    }
+    voices.sort_by_key(|v| height(*v));
+    voices
+}
+
+/// Returns the spelled notes of the chord in root position, each along
+/// with its scale degree.
+fn tones(chord: &Chord) -> Vec<(Degree, SpelledNote)> {
+    std::iter::once(degrees::I)
+        .chain(chord.quality.iter().map(|i| i.number))
+        .zip(chord.with_bass(None).spelled_notes())
+        .map(|(d, (n, _))| (d, n))
+        .collect()
+}
+
+/// Returns the first note of the chord with one of the `degrees`, trying
+/// them in order.
+fn tone(tones: &[(Degree, SpelledNote)], degrees: &[Degree]) -> Option<SpelledNote> {
+    degrees
+        .iter()
+        .find_map(|d| tones.iter().find(|(degree, _)| degree == d))
+        .map(|(_, n)| *n)
+}
+
+/// Stacks the notes on top of each other as closely as possible, with the
+/// first one sounding just above `floor`.
+fn stacked(notes: &[SpelledNote], floor: i32) -> Vec<Voice> {
+    let mut voices: Vec<Voice> = vec![];
+    for note in notes {
+        let floor = voices.last().map(|v| height(*v)).unwrap_or(floor);
+        voices.push(above(*note, floor));
+    }
+    voices
+}
+
+impl Voicing {
+    /// Returns a spread voicing from `low` to `high`, or `None` unless `low`
+    /// is at least an octave below `high`.
+    pub fn spread(low: LetterOctave, high: LetterOctave) -> Option<Voicing> {
+        SpreadRange::new(low, high).map(Voicing::Spread)
+    }
+
+    /// Returns the spelled notes of the chord in this voicing, along with
+    /// the octave each of them is written in, from the lowest upwards.
+    pub fn spelled_notes(&self, chord: &Chord) -> Vec<(SpelledNote, Octave)> {
+        use degrees::*;
+        let notes = chord.spelled_notes();
+        let lowest = height(notes[0]) - 1;
+        let close = stacked(&notes.iter().map(|(n, _)| *n).collect::<Vec<_>>(), lowest);
+        let root = height(chord.with_bass(None).spelled_notes()[0]);
+
+        match self {
+            Voicing::Close => close,
+            Voicing::Open => {
+                let second = close.len() - 1;
+                shifted(close, &[second], 1)
+            }
+            Voicing::Drop2 => shifted(close, &[2], -1),
+            Voicing::Drop3 => shifted(close, &[3], -1),
+            Voicing::Drop24 => shifted(close, &[2, 4], -1),
+            Voicing::Shell => {
+                let tones = tones(chord);
+                let mut shell = vec![notes[0].0];
+                shell.extend(tone(&tones, &[III, IV, II]));
+                shell.extend(tone(&tones, &[VII, VI]));
+                shell.dedup_by_key(|n| n.semitones().rem_euclid(12));
+                stacked(&shell, lowest)
+            }
+            Voicing::RootlessA | Voicing::RootlessB => {
+                let tones = tones(chord);
+                let ninth =
+                    tone(&tones, &[IX]).unwrap_or_else(|| tones[0].1.above(intervals::Maj9th));
+                let third = tone(&tones, &[III, IV, II]);
+                let fifth = tone(&tones, &[V]);
+                let seventh = tone(&tones, &[VII, VI]);
+
+                // Both voicings sit around the root, so that they can be
+                // alternated without jumping around.
+                let (order, floor) = match self {
+                    Voicing::RootlessA => ([third, fifth, seventh, Some(ninth)], root - 1),
+                    _ => ([seventh, Some(ninth), third, fifth], root - 12),
+                };
+                let notes: Vec<SpelledNote> = order.iter().flatten().copied().collect();
+                stacked(&notes, floor)
+            }
+            Voicing::Spread(range) => {
+                let low = pitch_height(range.low);
+                let high = pitch_height(range.high);
+                let bass = above(notes[0].0, low - 1);
+
+                let mut voices = vec![bass];
+                let upper = &close[1..];
+                for (i, (note, _)) in upper.iter().enumerate() {
+                    let target =
+                        height(bass) + (high - height(bass)) * (i as i32 + 1) / upper.len() as i32;
+                    let floor = height(*voices.last().unwrap());
+                    // The closest pitch to the target, as long as it is
+                    // above the previous note.
+                    let mut voice = above(*note, target - 7);
+                    while height(voice) <= floor {
+                        voice.1 += 1;
+                    }
+                    // Bring the note down into the range, as long as it stays
+                    // above the previous note.
+                    while height(voice) > high && height(voice) - 12 > floor {
+                        voice.1 -= 1;
+                    }
+                    voices.push(voice);
+                }
+                voices
+            }
+        }
+    }
+
+    /// Returns the notes of the chord in this voicing, from the lowest
+    /// upwards.
+    pub fn notes(&self, chord: &Chord) -> Vec<LetterOctave> {
+        self.spelled_notes(chord)
+            .into_iter()
+            .map(|(n, o)| n.letter_octave(o))
+            .collect()
+    }
+
+    /// Returns an object that writes the notes of the chord in this voicing
+    /// in the given `style` when formatted with `{}`.
+    pub fn display<'a>(&self, chord: &Chord, style: &'a NotationStyle) -> NotesDisplay<'a> {
+        NotesDisplay::new(self.spelled_notes(chord), style)
+    }
+}
+
+impl fmt::Display for Voicing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Voicing::Close => write!(f, "close"),
+            Voicing::Open => write!(f, "open"),
+            Voicing::Drop2 => write!(f, "drop 2"),
+            Voicing::Drop3 => write!(f, "drop 3"),
+            Voicing::Drop24 => write!(f, "drop 2 and 4"),
+            Voicing::Shell => write!(f, "shell"),
+            Voicing::RootlessA => write!(f, "rootless A"),
+            Voicing::RootlessB => write!(f, "rootless B"),
+            Voicing::Spread(SpreadRange { low, high }) => write!(
+                f,
+                "spread from {}{} to {}{}",
+                SpelledNote::from_letter(low.letter()),
+                low.octave(),
+                SpelledNote::from_letter(high.letter()),
+                high.octave()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music_theory::tests::chord;
+    use intervals::*;
+    use Letter::*;
+
+    fn voiced(voicing: Voicing, chord: &Chord) -> String {
+        voicing
+            .display(chord, &NotationStyle::default())
+            .to_string()
+    }
+
+    #[test]
+    fn close_and_drop_voicings() {
+        let cmaj7 = chord(C, vec![Maj3rd, Per5th, Maj7th]);
+        assert_eq!(voiced(Voicing::Close, &cmaj7), "C4 E4 G4 B4");
+        assert_eq!(voiced(Voicing::Open, &cmaj7), "C4 G4 B4 E5");
+        assert_eq!(voiced(Voicing::Drop2, &cmaj7), "G3 C4 E4 B4");
+        assert_eq!(voiced(Voicing::Drop3, &cmaj7), "E3 C4 G4 B4");
+        assert_eq!(voiced(Voicing::Drop24, &cmaj7), "C3 G3 E4 B4");
+
+        // Inversions and slash chords keep their bass in close voicing.
+        assert_eq!(
+            voiced(Voicing::Close, &cmaj7.inverted(1).unwrap()),
+            "E4 G4 B4 C5"
+        );
+        let c_over_d = chord(C, vec![Maj3rd, Per5th]).with_bass(Some(D));
+        assert_eq!(voiced(Voicing::Close, &c_over_d), "D3 C4 E4 G4");
+
+        let fsh_dim = chord(Fsh, vec![Min3rd, Dim5th]);
+        assert_eq!(voiced(Voicing::Drop2, &fsh_dim), "A3 F#4 C5");
+        assert_eq!(
+            Voicing::Drop2.notes(&fsh_dim),
+            vec![LetterOctave(A, 3), LetterOctave(Fsh, 4), LetterOctave(C, 5)]
+        );
+    }
+
+    #[test]
+    fn jazz_voicings() {
+        let g7 = chord(G, vec![Maj3rd, Per5th, Min7th]);
+        assert_eq!(voiced(Voicing::Shell, &g7), "G4 B4 F5");
+        assert_eq!(voiced(Voicing::RootlessA, &g7), "B4 D5 F5 A5");
+        assert_eq!(voiced(Voicing::RootlessB, &g7), "F4 A4 B4 D5");
+
+        let dm7b9 = chord(D, vec![Min3rd, Per5th, Min7th, Min9th]);
+        assert_eq!(voiced(Voicing::RootlessA, &dm7b9), "F4 A4 C5 Eb5");
+
+        let c6 = chord(C, vec![Maj3rd, Per5th, Maj6th]);
+        assert_eq!(voiced(Voicing::Shell, &c6), "C4 E4 A4");
+    }
+
+    #[test]
+    fn spread_voicing() {
+        let cmaj7 = chord(C, vec![Maj3rd, Per5th, Maj7th]);
+        let spread = Voicing::Spread(SpreadRange::default());
+        assert_eq!(voiced(spread, &cmaj7), "C2 E3 G4 B5");
+
+        // The bass stays at the bottom, even if that leaves the notes above
+        // it out of the range.
+        let narrow = Voicing::spread(LetterOctave(C, 4), LetterOctave(C, 5)).unwrap();
+        assert_eq!(voiced(narrow, &cmaj7), "C4 E4 G4 B4");
+        let b7 = chord(B, vec![Maj3rd, Per5th, Min7th]);
+        assert_eq!(voiced(narrow, &b7), "B4 D#5 F#5 A5");
+
+        // A range narrower than an octave, or upside down, is refused.
+        assert_eq!(
+            Voicing::spread(LetterOctave(C, 4), LetterOctave(F, 4)),
+            None
+        );
+        assert_eq!(
+            Voicing::spread(LetterOctave(C, 6), LetterOctave(C, 2)),
+            None
+        );
+
+        // The top note is brought down an octave to fit.
+        let am7_over_g = chord(A, vec![Min3rd, Per5th, Min7th]).with_bass(Some(G));
+        assert_eq!(voiced(spread, &am7_over_g), "G2 A3 C5 E5");
+        assert_eq!(spread.to_string(), "spread from C2 to C6");
+    }
+}